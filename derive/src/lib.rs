@@ -0,0 +1,232 @@
+//! `#[derive(Entity)]` for burncloud-database. Use it through the `derive` feature of
+//! `burncloud-database`, which re-exports the macro next to the `Entity` trait.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, GenericArgument, Ident, LitStr, PathArguments, Type};
+
+/// Implements `Entity` and `sqlx::FromRow` for a struct with named fields.
+///
+/// Struct attributes:
+/// - `#[entity(table = "models")]` - the table, defaulting to the struct name in snake_case
+/// - `#[entity(primary_key = "model_id")]` - the primary key column, defaulting to `id`
+///
+/// Field attributes:
+/// - `#[entity(primary_key)]` - this field is the primary key
+/// - `#[entity(rename = "display_name")]` - the column name, defaulting to the field name
+/// - `#[entity(json)]` - stored as JSON text through serde
+/// - `#[entity(skip)]` - not stored; filled with `Default::default()` when loaded
+/// - `#[entity(created_at)]` / `#[entity(updated_at)]` - set to the current time by the
+///   repository on insert / on insert and update
+///
+/// Other fields are written with `Value::from` and read with `from_column`.
+#[proc_macro_derive(Entity, attributes(entity))]
+pub fn derive_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    rename: Option<String>,
+    primary_key: bool,
+    json: bool,
+    skip: bool,
+    created_at: bool,
+    updated_at: bool,
+}
+
+struct Column<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    name: String,
+    options: FieldOptions,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "Entity cannot be derived for generic types"));
+    }
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "Entity can only be derived for structs with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "Entity can only be derived for structs")),
+    };
+
+    let mut table = None;
+    let mut primary_key = None;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("entity")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("primary_key") {
+                primary_key = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("expected `table` or `primary_key`"));
+            }
+            Ok(())
+        })?;
+    }
+    let table = table.unwrap_or_else(|| snake_case(&name.to_string()));
+
+    let columns = fields.iter().map(column).collect::<syn::Result<Vec<_>>>()?;
+    let stored: Vec<&Column> = columns.iter().filter(|c| !c.options.skip).collect();
+
+    for c in &columns {
+        if c.options.primary_key {
+            if primary_key.is_some() {
+                return Err(syn::Error::new_spanned(c.ident, "more than one primary key"));
+            }
+            primary_key = Some(c.name.clone());
+        }
+    }
+    let primary_key = primary_key.unwrap_or_else(|| "id".to_string());
+    let Some(key) = stored.iter().find(|c| c.name == primary_key) else {
+        return Err(syn::Error::new_spanned(
+            name,
+            format!("no stored field for primary key column `{}`; mark one with #[entity(primary_key)]", primary_key),
+        ));
+    };
+    let id_type = option_inner(key.ty).unwrap_or(key.ty);
+
+    let created_at = timestamp(&stored, |o| o.created_at)?;
+    let updated_at = timestamp(&stored, |o| o.updated_at)?;
+
+    let column_names = stored.iter().map(|c| &c.name);
+    let encode = stored.iter().map(|c| {
+        let ident = c.ident;
+        if c.options.json {
+            quote!(::burncloud_database::Value::json(&self.#ident)?)
+        } else {
+            quote!(::burncloud_database::Value::from(::std::clone::Clone::clone(&self.#ident)))
+        }
+    });
+    let decode = columns.iter().map(|c| {
+        let ident = c.ident;
+        let column = &c.name;
+        if c.options.skip {
+            quote!(#ident: ::std::default::Default::default())
+        } else if c.options.json {
+            quote!(#ident: ::burncloud_database::from_json_column(row, #column)?)
+        } else {
+            quote!(#ident: ::burncloud_database::from_column(row, #column)?)
+        }
+    });
+
+    Ok(quote! {
+        impl ::burncloud_database::Entity for #name {
+            type Id = #id_type;
+
+            const TABLE: &'static str = #table;
+            const PRIMARY_KEY: &'static str = #primary_key;
+            const COLUMNS: &'static [&'static str] = &[#(#column_names),*];
+            const CREATED_AT: ::std::option::Option<&'static str> = #created_at;
+            const UPDATED_AT: ::std::option::Option<&'static str> = #updated_at;
+
+            fn column_values(&self) -> ::burncloud_database::Result<::std::vec::Vec<::burncloud_database::Value>> {
+                ::std::result::Result::Ok(::std::vec![#(#encode),*])
+            }
+
+            fn from_sql_row(row: &::burncloud_database::sqlx::sqlite::SqliteRow) -> ::burncloud_database::Result<Self> {
+                ::std::result::Result::Ok(Self { #(#decode),* })
+            }
+        }
+
+        impl<'r> ::burncloud_database::sqlx::FromRow<'r, ::burncloud_database::sqlx::sqlite::SqliteRow> for #name {
+            fn from_row(
+                row: &'r ::burncloud_database::sqlx::sqlite::SqliteRow,
+            ) -> ::std::result::Result<Self, ::burncloud_database::sqlx::Error> {
+                <Self as ::burncloud_database::Entity>::from_sql_row(row)
+                    .map_err(|e| ::burncloud_database::sqlx::Error::Decode(::std::boxed::Box::new(e)))
+            }
+        }
+    })
+}
+
+fn column(field: &Field) -> syn::Result<Column<'_>> {
+    let ident = field.ident.as_ref().expect("named field");
+    let mut options = FieldOptions::default();
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("entity")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("primary_key") {
+                options.primary_key = true;
+            } else if meta.path.is_ident("json") {
+                options.json = true;
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+            } else if meta.path.is_ident("created_at") {
+                options.created_at = true;
+            } else if meta.path.is_ident("updated_at") {
+                options.updated_at = true;
+            } else {
+                return Err(meta.error(
+                    "expected `rename`, `primary_key`, `json`, `skip`, `created_at` or `updated_at`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    if options.skip && (options.primary_key || options.created_at || options.updated_at) {
+        return Err(syn::Error::new_spanned(ident, "a skipped field cannot be a key or timestamp"));
+    }
+
+    let name = options.rename.clone().unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+    Ok(Column {
+        ident,
+        ty: &field.ty,
+        name,
+        options,
+    })
+}
+
+// The column marked by `is_marked`, as `Some("name")` or `None`
+fn timestamp(columns: &[&Column], is_marked: impl Fn(&FieldOptions) -> bool) -> syn::Result<TokenStream2> {
+    let mut marked = columns.iter().filter(|c| is_marked(&c.options));
+    match (marked.next(), marked.next()) {
+        (None, _) => Ok(quote!(::std::option::Option::None)),
+        (Some(c), None) => {
+            let name = &c.name;
+            Ok(quote!(::std::option::Option::Some(#name)))
+        }
+        (Some(_), Some(c)) => Err(syn::Error::new_spanned(c.ident, "duplicate timestamp field")),
+    }
+}
+
+// `T` for a field of type `Option<T>`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, ch) in name.char_indices() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
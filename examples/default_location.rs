@@ -1,4 +1,4 @@
-use burncloud_database::{Result, Database, create_default_database};
+use burncloud_database::{Result, Database, UpdatePolicy, create_default_database, get_default_database_path};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -14,8 +14,10 @@ async fn main() -> Result<()> {
             let result = db.execute_query("CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT)").await?;
             println!("✓ Settings table created: {:?}", result);
 
-            let insert_result = db.execute_query("INSERT OR REPLACE INTO settings (key, value) VALUES ('app_version', '1.0.0')").await?;
-            println!("✓ Setting inserted: {:?}", insert_result);
+            // An upsert updates the existing row in place instead of replacing it
+            let setting = serde_json::json!({ "key": "app_version", "value": "1.0.0" });
+            let upserted = db.upsert("settings", &setting, &["key"], &UpdatePolicy::overwrite()).await?;
+            println!("✓ Setting upserted: {} row(s)", upserted);
 
             db.close().await?;
             println!("✓ Database closed successfully\n");
@@ -48,15 +50,9 @@ async fn main() -> Result<()> {
     println!("\n3. Default database location:");
     println!("Platform: {}", if cfg!(target_os = "windows") { "Windows" } else { "Linux/Unix" });
 
-    // This uses internal function logic to show the path
-    let expected_path = if cfg!(target_os = "windows") {
-        std::env::var("USERPROFILE").map(|profile|
-            format!("{}\\AppData\\Local\\BurnCloud\\data.db", profile)
-        ).unwrap_or_else(|_| "Could not determine USERPROFILE".to_string())
-    } else {
-        dirs::home_dir().map(|home|
-            format!("{}/.burncloud/data.db", home.display())
-        ).unwrap_or_else(|| "Could not determine home directory".to_string())
+    let expected_path = match get_default_database_path() {
+        Ok(path) => path.display().to_string(),
+        Err(e) => format!("Could not determine default path: {}", e),
     };
 
     println!("Default path: {}", expected_path);
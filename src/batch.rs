@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::SqliteConnection;
+
+use crate::error::{DatabaseError, Result};
+use crate::executor;
+use crate::ser::record_fields;
+use crate::value::Value;
+
+/// SQLite's default `SQLITE_MAX_VARIABLE_NUMBER` since 3.32.
+pub const MAX_BIND_PARAMS: usize = 32766;
+
+/// What a batch insert does with rows that violate a UNIQUE or PRIMARY KEY constraint.
+///
+/// See <https://www.sqlite.org/lang_conflict.html>. `Abort` fails the whole batch, `Ignore`
+/// skips the conflicting rows and `Replace` deletes the existing rows first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    #[default]
+    Abort,
+    Ignore,
+    Replace,
+}
+
+impl OnConflict {
+    pub(crate) fn insert_sql(self) -> &'static str {
+        match self {
+            OnConflict::Abort => "INSERT INTO",
+            OnConflict::Ignore => "INSERT OR IGNORE INTO",
+            OnConflict::Replace => "INSERT OR REPLACE INTO",
+        }
+    }
+}
+
+/// Quotes a table or column name for use in SQL, doubling any embedded `"`.
+///
+/// The whole name is one identifier, so `main.users` quotes to `"main.users"`.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// A multi-row INSERT, split into statements that stay under MAX_BIND_PARAMS
+pub(crate) struct Batch {
+    prefix: String,
+    columns: usize,
+    // Row-major, `columns` values per row
+    values: Vec<Value>,
+}
+
+impl Batch {
+    pub(crate) fn new<I, R>(on_conflict: OnConflict, table: &str, columns: &[&str], rows: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator,
+        R::Item: Into<Value>,
+    {
+        if columns.is_empty() || columns.len() > MAX_BIND_PARAMS {
+            return Err(DatabaseError::InvalidData {
+                message: format!("batch insert needs 1 to {} columns, got {}", MAX_BIND_PARAMS, columns.len()),
+            });
+        }
+
+        let mut values = Vec::new();
+        for (index, row) in rows.into_iter().enumerate() {
+            let before = values.len();
+            values.extend(row.into_iter().map(Into::into));
+            if values.len() - before != columns.len() {
+                return Err(DatabaseError::InvalidData {
+                    message: format!("row {} has {} values for {} columns", index, values.len() - before, columns.len()),
+                });
+            }
+        }
+
+        let columns_sql = columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ");
+        Ok(Self {
+            prefix: format!("{} {} ({}) VALUES ", on_conflict.insert_sql(), quote_identifier(table), columns_sql),
+            columns: columns.len(),
+            values,
+        })
+    }
+
+    // Columns come from the first record's fields, in declaration order
+    pub(crate) fn from_records<I>(on_conflict: OnConflict, table: &str, records: I) -> Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        let mut columns: Vec<String> = Vec::new();
+        let mut rows = Vec::new();
+        for (index, record) in records.into_iter().enumerate() {
+            let fields = record_fields(&record, index)?;
+            if index == 0 {
+                columns = fields.iter().map(|(c, _)| c.clone()).collect();
+            }
+            let mut fields: HashMap<String, Value> = fields.into_iter().collect();
+            if fields.len() != columns.len() || !columns.iter().all(|c| fields.contains_key(c)) {
+                return Err(DatabaseError::InvalidData {
+                    message: format!("record {} has different fields than record 0", index),
+                });
+            }
+            let row: Vec<Value> = columns
+                .iter()
+                .map(|c| fields.remove(c).unwrap_or(Value::Null))
+                .collect();
+            rows.push(row);
+        }
+
+        if rows.is_empty() {
+            return Ok(Self {
+                prefix: String::new(),
+                columns: 0,
+                values: Vec::new(),
+            });
+        }
+        let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+        Self::new(on_conflict, table, &columns, rows)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // Returns the number of rows inserted
+    pub(crate) async fn run(&self, conn: &mut SqliteConnection) -> Result<u64> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+        let placeholders = format!("({})", vec!["?"; self.columns].join(", "));
+        let rows_per_chunk = MAX_BIND_PARAMS / self.columns;
+        let mut inserted = 0;
+        for chunk in self.values.chunks(rows_per_chunk * self.columns) {
+            let rows = chunk.len() / self.columns;
+            let sql = format!("{}{}", self.prefix, vec![placeholders.as_str(); rows].join(", "));
+            inserted += executor::execute(&mut *conn, &sql, Some(chunk)).await?.rows_affected();
+        }
+        Ok(inserted)
+    }
+}
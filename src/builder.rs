@@ -0,0 +1,223 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use uuid::Uuid;
+
+use crate::database::{
+    create_directory_if_not_exists, get_default_database_path, get_legacy_database_path, Database, DatabaseConnection,
+};
+use crate::error::{DatabaseError, Result};
+use crate::legacy::relocate_legacy_database;
+use crate::pragma::PragmaProfile;
+use crate::retry::RetryPolicy;
+
+pub(crate) const IN_MEMORY_PATH: &str = ":memory:";
+
+/// Configures where a [`Database`] lives and how its connection pool behaves.
+///
+/// ```no_run
+/// # async fn example() -> burncloud_database::Result<()> {
+/// use burncloud_database::DatabaseBuilder;
+///
+/// let db = DatabaseBuilder::new()
+///     .path("./data/burncloud.db")
+///     .max_connections(4)
+///     .build()
+///     .await?;
+/// # db.close().await
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatabaseBuilder {
+    path: Option<PathBuf>,
+    in_memory: bool,
+    min_connections: u32,
+    max_connections: u32,
+    acquire_timeout: Duration,
+    idle_timeout: Option<Duration>,
+    create_if_missing: bool,
+    pragmas: PragmaProfile,
+    retry_policy: RetryPolicy,
+    cursor_secret: CursorSecret,
+}
+
+// Kept out of Debug output
+#[derive(Clone)]
+struct CursorSecret(Vec<u8>);
+
+impl CursorSecret {
+    fn random() -> Self {
+        // v4 UUIDs come from the OS random number generator
+        Self([Uuid::new_v4().into_bytes(), Uuid::new_v4().into_bytes()].concat())
+    }
+}
+
+impl std::fmt::Debug for CursorSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CursorSecret(..)")
+    }
+}
+
+impl Default for DatabaseBuilder {
+    fn default() -> Self {
+        Self {
+            path: None,
+            in_memory: false,
+            min_connections: 0,
+            max_connections: 10,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Some(Duration::from_secs(10 * 60)),
+            create_if_missing: true,
+            pragmas: PragmaProfile::default(),
+            retry_policy: RetryPolicy::none(),
+            cursor_secret: CursorSecret::random(),
+        }
+    }
+}
+
+impl DatabaseBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses a database file at `path` instead of the platform default location.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self.in_memory = false;
+        self
+    }
+
+    /// Uses a private in-memory database that lives as long as the returned `Database`.
+    pub fn in_memory(mut self) -> Self {
+        self.path = None;
+        self.in_memory = true;
+        self
+    }
+
+    pub fn min_connections(mut self, min: u32) -> Self {
+        self.min_connections = min;
+        self
+    }
+
+    pub fn max_connections(mut self, max: u32) -> Self {
+        self.max_connections = max;
+        self
+    }
+
+    /// How long to wait for a free pooled connection before failing.
+    pub fn acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.acquire_timeout = timeout;
+        self
+    }
+
+    /// How long an unused connection stays open; `None` keeps connections indefinitely.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Whether a missing database file (and its directory) should be created.
+    pub fn create_if_missing(mut self, create: bool) -> Self {
+        self.create_if_missing = create;
+        self
+    }
+
+    /// PRAGMA settings applied to every pooled connection.
+    pub fn pragmas(mut self, pragmas: PragmaProfile) -> Self {
+        self.pragmas = pragmas;
+        self
+    }
+
+    pub fn pragma_profile(&self) -> &PragmaProfile {
+        &self.pragmas
+    }
+
+    /// How `Database` query helpers and `retry_transaction` react to busy/locked errors.
+    /// Defaults to no retries.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    pub(crate) fn retry(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// The key that signs keyset pagination cursors.
+    ///
+    /// **Without it, every cursor stops working when the process restarts:** the default
+    /// is a random key per builder, so cursors are only accepted by databases built from
+    /// the same builder. Servers should load a stable secret from their configuration and
+    /// share it between all instances serving the same API.
+    pub fn cursor_secret(mut self, secret: impl AsRef<[u8]>) -> Self {
+        self.cursor_secret = CursorSecret(secret.as_ref().to_vec());
+        self
+    }
+
+    pub(crate) fn cursor_key(&self) -> &[u8] {
+        &self.cursor_secret.0
+    }
+
+    pub async fn build(self) -> Result<Database> {
+        if self.max_connections == 0 || self.min_connections > self.max_connections {
+            return Err(DatabaseError::InvalidData {
+                message: format!(
+                    "invalid pool size: min_connections {} and max_connections {}",
+                    self.min_connections, self.max_connections
+                ),
+            });
+        }
+
+        let database_path = if self.in_memory {
+            IN_MEMORY_PATH.to_string()
+        } else {
+            let path = match &self.path {
+                Some(path) => path.clone(),
+                None => {
+                    let path = get_default_database_path()?;
+                    match get_legacy_database_path() {
+                        Some(legacy) => relocate_legacy_database(&legacy, &path).await?,
+                        None => path,
+                    }
+                }
+            };
+            if self.create_if_missing {
+                create_directory_if_not_exists(&path)?;
+            }
+            path.to_string_lossy().to_string()
+        };
+
+        let mut db = Database::from_config(self, database_path);
+        db.initialize().await?;
+        Ok(db)
+    }
+
+    pub(crate) fn pool_options(&self) -> SqlitePoolOptions {
+        SqlitePoolOptions::new()
+            .min_connections(self.min_connections)
+            .max_connections(self.max_connections)
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+    }
+
+    pub(crate) async fn connect(&self, database_path: &str) -> Result<DatabaseConnection> {
+        let mut pool_options = self.pool_options();
+
+        let connect_options = if database_path == IN_MEMORY_PATH {
+            // The database disappears with its last connection, so keep one open for good
+            pool_options = pool_options
+                .min_connections(self.min_connections.max(1))
+                .idle_timeout(None)
+                .max_lifetime(None);
+            SqliteConnectOptions::from_str("sqlite::memory:")?
+        } else {
+            SqliteConnectOptions::new()
+                .filename(database_path)
+                .create_if_missing(self.create_if_missing)
+        };
+
+        DatabaseConnection::connect_with(self.pragmas.apply(connect_options), pool_options).await
+    }
+}
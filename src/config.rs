@@ -0,0 +1,187 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::builder::DatabaseBuilder;
+use crate::database::get_default_database_dir;
+use crate::error::{DatabaseError, Result};
+
+/// Overrides the database location with a SQLite URL (`sqlite:///srv/burncloud/data.db`,
+/// `sqlite::memory:`) or a plain file path.
+pub const DATABASE_URL_ENV: &str = "BURNCLOUD_DATABASE_URL";
+
+/// Overrides the BurnCloud data directory; the database becomes `$BURNCLOUD_HOME/data.db`.
+pub const HOME_ENV: &str = "BURNCLOUD_HOME";
+
+/// Overrides the location of the configuration file.
+pub const CONFIG_ENV: &str = "BURNCLOUD_CONFIG";
+
+pub const CONFIG_FILE_NAME: &str = "burncloud.toml";
+
+const DATABASE_FILE_NAME: &str = "data.db";
+
+/// The `[database]` table of `burncloud.toml`.
+///
+/// ```toml
+/// [database]
+/// path = "/var/lib/burncloud/data.db"
+/// max_connections = 8
+/// acquire_timeout_secs = 10
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub path: Option<PathBuf>,
+    pub min_connections: Option<u32>,
+    pub max_connections: Option<u32>,
+    pub acquire_timeout_secs: Option<u64>,
+    pub idle_timeout_secs: Option<u64>,
+    pub create_if_missing: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    database: DatabaseConfig,
+}
+
+impl DatabaseConfig {
+    /// Reads the `[database]` table from a TOML file. Other tables are ignored, and a relative
+    /// `path` is resolved against the directory containing the file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let mut config = Self::from_toml(&contents)
+            .map_err(|e| DatabaseError::Configuration(format!("{}: {}", path.display(), e)))?;
+
+        if let (Some(db_path), Some(dir)) = (&config.path, path.parent()) {
+            if db_path.is_relative() {
+                config.path = Some(dir.join(db_path));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self> {
+        let file: ConfigFile = toml::from_str(contents).map_err(|e| DatabaseError::Configuration(e.to_string()))?;
+        Ok(file.database)
+    }
+
+    /// Applies the settings present in this config on top of `builder`.
+    pub fn apply(&self, mut builder: DatabaseBuilder) -> DatabaseBuilder {
+        if let Some(path) = &self.path {
+            builder = builder.path(path);
+        }
+        if let Some(min) = self.min_connections {
+            builder = builder.min_connections(min);
+        }
+        if let Some(max) = self.max_connections {
+            builder = builder.max_connections(max);
+        }
+        if let Some(secs) = self.acquire_timeout_secs {
+            builder = builder.acquire_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.idle_timeout_secs {
+            builder = builder.idle_timeout(Some(Duration::from_secs(secs)));
+        }
+        if let Some(create) = self.create_if_missing {
+            builder = builder.create_if_missing(create);
+        }
+        builder
+    }
+}
+
+/// Where `burncloud.toml` is looked up: `$BURNCLOUD_CONFIG`, then `$BURNCLOUD_HOME/burncloud.toml`,
+/// then `burncloud.toml` in the platform config directory (`$XDG_CONFIG_HOME/burncloud` on
+/// Linux, the default BurnCloud directory elsewhere).
+pub fn config_file_path() -> Result<PathBuf> {
+    if let Some(path) = env_var(CONFIG_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+    let dir = match env_var(HOME_ENV) {
+        Some(home) => PathBuf::from(home),
+        None => default_config_dir()?,
+    };
+    Ok(dir.join(CONFIG_FILE_NAME))
+}
+
+fn default_config_dir() -> Result<PathBuf> {
+    if cfg!(target_os = "linux") {
+        // Linux: $XDG_CONFIG_HOME/burncloud, falling back to ~/.config/burncloud
+        Ok(dirs::config_dir()
+            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
+            .join("burncloud"))
+    } else {
+        get_default_database_dir()
+    }
+}
+
+impl DatabaseBuilder {
+    /// A builder configured from the environment and `burncloud.toml`.
+    ///
+    /// The database location is resolved in this order, first match wins:
+    ///
+    /// 1. `BURNCLOUD_DATABASE_URL`
+    /// 2. `BURNCLOUD_HOME` (as `$BURNCLOUD_HOME/data.db`)
+    /// 3. `path` in the `[database]` table of `burncloud.toml`
+    /// 4. the platform default location
+    ///
+    /// Pool options come from `burncloud.toml` when present. Setters called on the returned
+    /// builder override everything. A missing config file is not an error; a malformed one is.
+    pub fn from_env() -> Result<Self> {
+        let config_path = config_file_path()?;
+        let config = if config_path.is_file() {
+            DatabaseConfig::from_file(&config_path)?
+        } else if env_var(CONFIG_ENV).is_some() {
+            return Err(DatabaseError::Configuration(format!(
+                "{} points to {}, which does not exist",
+                CONFIG_ENV,
+                config_path.display()
+            )));
+        } else {
+            DatabaseConfig::default()
+        };
+
+        let mut builder = config.apply(DatabaseBuilder::new());
+
+        if let Some(url) = env_var(DATABASE_URL_ENV) {
+            builder = match parse_database_url(&url)? {
+                Some(path) => builder.path(path),
+                None => builder.in_memory(),
+            };
+        } else if let Some(home) = env_var(HOME_ENV) {
+            builder = builder.path(PathBuf::from(home).join(DATABASE_FILE_NAME));
+        }
+
+        Ok(builder)
+    }
+}
+
+// Returns the file path named by a database URL, or None for an in-memory database
+fn parse_database_url(url: &str) -> Result<Option<PathBuf>> {
+    if url == "sqlite::memory:" || url == ":memory:" {
+        return Ok(None);
+    }
+
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+        .unwrap_or(url);
+
+    if path.contains('?') {
+        return Err(DatabaseError::Configuration(format!(
+            "{} does not support URL parameters ({}); set pool options in {}",
+            DATABASE_URL_ENV, url, CONFIG_FILE_NAME
+        )));
+    }
+    if path.is_empty() {
+        return Err(DatabaseError::Configuration(format!("{} has no database path", DATABASE_URL_ENV)));
+    }
+
+    Ok(Some(PathBuf::from(path)))
+}
+
+fn env_var(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.trim().is_empty())
+}
@@ -1,6 +1,19 @@
-use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use std::str::FromStr;
 
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePoolOptions}, SqlitePool};
+
+use crate::batch::Batch;
+use crate::builder::DatabaseBuilder;
 use crate::error::{DatabaseError, Result};
+use crate::executor;
+use crate::migration::{AppliedMigration, BoxFuture, Migration, Migrator};
+use crate::pagination::{self, Page, PageRequest};
+use crate::pragma::{EffectivePragmas, PragmaProfile};
+use crate::repository::{Entity, Repository};
+use crate::retry::RetryPolicy;
+use crate::script;
+use crate::transaction::{Transaction, TransactionBehavior};
+use crate::value::Value;
 
 #[derive(Clone)]
 pub struct DatabaseConnection {
@@ -8,11 +21,15 @@ pub struct DatabaseConnection {
 }
 
 impl DatabaseConnection {
+    // Uses the DatabaseBuilder defaults for pool size, timeouts and pragmas
     pub async fn new(database_url: &str) -> Result<Self> {
-        let pool = SqlitePoolOptions::new()
-            .max_connections(10)
-            .connect(database_url)
-            .await?;
+        let config = DatabaseBuilder::default();
+        let options = SqliteConnectOptions::from_str(database_url)?;
+        Self::connect_with(config.pragma_profile().apply(options), config.pool_options()).await
+    }
+
+    pub async fn connect_with(options: SqliteConnectOptions, pool_options: SqlitePoolOptions) -> Result<Self> {
+        let pool = pool_options.connect_with(options).await?;
 
         Ok(Self { pool })
     }
@@ -26,43 +43,74 @@ impl DatabaseConnection {
     }
 }
 
+// Runs a query helper's future on the pool, retried per the retry policy; see
+// executor::query_helpers
+macro_rules! on_pool {
+    ($db:ident, |$exec:ident| $future:expr) => {{
+        let $exec = $db.connection()?.pool();
+        $db.retry_policy().run(|| $future).await
+    }};
+    (stream $db:ident, |$exec:ident| $future:expr) => {{
+        let $exec = $db.connection()?.pool();
+        $future
+    }};
+}
+
 pub struct Database {
     connection: Option<DatabaseConnection>,
     database_path: String,
+    config: DatabaseBuilder,
+    migrator: Migrator,
 }
 
 impl Database {
+    // Opens the database resolved from BURNCLOUD_* environment variables, burncloud.toml
+    // or the platform default location (see DatabaseBuilder::from_env)
     pub async fn new() -> Result<Self> {
-        let default_path = get_default_database_path()?;
+        DatabaseBuilder::from_env()?.build().await
+    }
 
-        create_directory_if_not_exists(&default_path)?;
+    pub fn builder() -> DatabaseBuilder {
+        DatabaseBuilder::new()
+    }
 
-        let path = default_path.to_string_lossy().to_string();
-        let mut db = Self {
+    pub(crate) fn from_config(config: DatabaseBuilder, database_path: String) -> Self {
+        Self {
             connection: None,
-            database_path: path,
-        };
-        db.initialize().await?;
-        Ok(db)
+            database_path,
+            config,
+            migrator: Migrator::new(),
+        }
     }
 
     pub async fn initialize(&mut self) -> Result<()> {
-        let database_url = if self.database_path == ":memory:" {
-            "sqlite::memory:".to_string()
-        } else {
-            // Normalize path separators for SQLite URL
-            // SQLite requires forward slashes even on Windows
-            let normalized_path = self.database_path.replace('\\', "/");
-            // Add mode=rwc to create the database file if it doesn't exist
-            format!("sqlite://{}?mode=rwc", normalized_path)
-        };
-
-        let connection = DatabaseConnection::new(&database_url).await?;
+        let connection = self.config.connect(&self.database_path).await?;
 
         self.connection = Some(connection);
         Ok(())
     }
 
+    // File path of the database, or ":memory:" for in-memory databases
+    pub fn database_path(&self) -> &str {
+        &self.database_path
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.database_path == crate::builder::IN_MEMORY_PATH
+    }
+
+    // The PRAGMA settings requested for every connection
+    pub fn pragma_profile(&self) -> &PragmaProfile {
+        self.config.pragma_profile()
+    }
+
+    // Reads back the PRAGMA values actually in effect on a pooled connection
+    pub async fn effective_pragmas(&self) -> Result<EffectivePragmas> {
+        let conn = self.connection()?;
+        let mut pooled = conn.pool().acquire().await?;
+        EffectivePragmas::read(&mut pooled).await
+    }
+
     pub fn connection(&self) -> Result<&DatabaseConnection> {
         self.connection
             .as_ref()
@@ -70,79 +118,196 @@ impl Database {
     }
 
     pub async fn create_tables(&self) -> Result<()> {
-        let _conn = self.connection()?;
-
+        self.migrate().await?;
         Ok(())
     }
 
-    pub async fn close(mut self) -> Result<()> {
-        if let Some(connection) = self.connection.take() {
-            connection.close().await;
+    pub fn add_migration(&mut self, migration: Migration) -> Result<()> {
+        self.migrator.add(migration)
+    }
+
+    pub fn add_migrations(&mut self, migrations: impl IntoIterator<Item = Migration>) -> Result<()> {
+        for migration in migrations {
+            self.migrator.add(migration)?;
         }
         Ok(())
     }
 
-    pub async fn execute_query(&self, query: &str) -> Result<sqlx::sqlite::SqliteQueryResult> {
-        let conn = self.connection()?;
-        let result = sqlx::query(query).execute(conn.pool()).await?;
-        Ok(result)
+    pub fn migrator(&self) -> &Migrator {
+        &self.migrator
     }
 
-    pub async fn execute_query_with_params(&self, query: &str, params: Vec<String>) -> Result<sqlx::sqlite::SqliteQueryResult> {
+    // Applies every registered migration that has not been applied yet
+    pub async fn migrate(&self) -> Result<Vec<i64>> {
         let conn = self.connection()?;
-        let mut query_builder = sqlx::query(query);
-
-        for param in params {
-            query_builder = query_builder.bind(param);
-        }
+        self.migrator.run(conn).await
+    }
 
-        let result = query_builder.execute(conn.pool()).await?;
-        Ok(result)
+    // Rolls the schema forward or backward to exactly `version` (0 reverts everything)
+    pub async fn migrate_to(&self, version: i64) -> Result<Vec<i64>> {
+        let conn = self.connection()?;
+        self.migrator.run_to(conn, version).await
     }
 
-    pub async fn query(&self, query: &str) -> Result<Vec<sqlx::sqlite::SqliteRow>> {
+    // Fails if the recorded history no longer matches the registered migrations
+    pub async fn verify_migrations(&self) -> Result<()> {
         let conn = self.connection()?;
-        let rows = sqlx::query(query).fetch_all(conn.pool()).await?;
-        Ok(rows)
+        self.migrator.verify(conn).await
     }
 
-    pub async fn query_with_params(&self, query: &str, params: Vec<String>) -> Result<Vec<sqlx::sqlite::SqliteRow>> {
+    pub async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>> {
         let conn = self.connection()?;
-        let mut query_builder = sqlx::query(query);
+        self.migrator.applied(conn).await
+    }
+
+    pub async fn schema_version(&self) -> Result<Option<i64>> {
+        Ok(self.applied_migrations().await?.last().map(|m| m.version))
+    }
 
-        for param in params {
-            query_builder = query_builder.bind(param);
+    pub async fn close(mut self) -> Result<()> {
+        if let Some(connection) = self.connection.take() {
+            connection.close().await;
         }
+        Ok(())
+    }
+
+    pub async fn begin(&self) -> Result<Transaction> {
+        self.begin_with(TransactionBehavior::Deferred).await
+    }
 
-        let rows = query_builder.fetch_all(conn.pool()).await?;
-        Ok(rows)
+    pub async fn begin_with(&self, behavior: TransactionBehavior) -> Result<Transaction> {
+        let pool = self.connection()?.pool();
+        // Nothing has run yet if BEGIN fails, so it is always safe to retry
+        self.retry_policy()
+            .run(|| async { Transaction::begin(pool.acquire().await?, behavior).await })
+            .await
     }
 
-    pub async fn fetch_one<T>(&self, query: &str) -> Result<T>
+    // Runs `f` inside a transaction, committing if it returns Ok and rolling back on Err:
+    //
+    //     db.transaction(|tx| Box::pin(async move {
+    //         tx.execute_query("...").await?;
+    //         Ok(())
+    //     })).await?;
+    pub async fn transaction<F, R>(&self, f: F) -> Result<R>
     where
-        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+        F: for<'t> FnOnce(&'t mut Transaction) -> BoxFuture<'t, Result<R>>,
     {
-        let conn = self.connection()?;
-        let result = sqlx::query_as::<_, T>(query).fetch_one(conn.pool()).await?;
-        Ok(result)
+        self.transaction_with(TransactionBehavior::Deferred, f).await
     }
 
-    pub async fn fetch_all<T>(&self, query: &str) -> Result<Vec<T>>
+    pub async fn transaction_with<F, R>(&self, behavior: TransactionBehavior, f: F) -> Result<R>
     where
-        T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+        F: for<'t> FnOnce(&'t mut Transaction) -> BoxFuture<'t, Result<R>>,
     {
-        let conn = self.connection()?;
-        let results = sqlx::query_as::<_, T>(query).fetch_all(conn.pool()).await?;
-        Ok(results)
+        let tx = self.begin_with(behavior).await?;
+        run_in_transaction(tx, f).await
+    }
+
+    // Like transaction, but reruns the whole closure in a fresh transaction when it fails
+    // with a busy/locked error, as allowed by the retry policy. The closure must therefore
+    // be callable more than once and free of side effects outside the database.
+    pub async fn retry_transaction<F, R>(&self, f: F) -> Result<R>
+    where
+        F: for<'t> Fn(&'t mut Transaction) -> BoxFuture<'t, Result<R>>,
+    {
+        self.retry_transaction_with(TransactionBehavior::Deferred, f).await
+    }
+
+    pub async fn retry_transaction_with<F, R>(&self, behavior: TransactionBehavior, f: F) -> Result<R>
+    where
+        F: for<'t> Fn(&'t mut Transaction) -> BoxFuture<'t, Result<R>>,
+    {
+        let pool = self.connection()?.pool();
+        self.retry_policy()
+            .run(|| async {
+                let tx = Transaction::begin(pool.acquire().await?, behavior).await?;
+                run_in_transaction(tx, &f).await
+            })
+            .await
     }
 
-    pub async fn fetch_optional<T>(&self, query: &str) -> Result<Option<T>>
+    // Runs a multi-statement script in one transaction; returns how many statements ran. A
+    // BEGIN ... COMMIT around the whole script is skipped, other transaction control rejected
+    pub async fn execute_script(&self, sql: &str) -> Result<usize> {
+        // Scripts write, so take the write lock up front rather than failing halfway through
+        let sql = sql.to_string();
+        self.retry_transaction_with(TransactionBehavior::Immediate, move |tx| {
+            let sql = sql.clone();
+            Box::pin(async move { script::run(tx, &sql).await })
+        })
+        .await
+    }
+
+    pub async fn execute_script_file(&self, path: impl AsRef<std::path::Path>) -> Result<usize> {
+        let sql = tokio::fs::read_to_string(path).await?;
+        self.execute_script(&sql).await
+    }
+
+    // Runs the batch in its own IMMEDIATE transaction, retried as a whole on busy/locked errors
+    async fn run_batch(&self, batch: Batch) -> Result<u64> {
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        let pool = self.connection()?.pool();
+        self.retry_policy()
+            .run(|| async {
+                let mut tx = Transaction::begin(pool.acquire().await?, TransactionBehavior::Immediate).await?;
+                match batch.run(tx.connection()?).await {
+                    Ok(inserted) => {
+                        tx.commit().await?;
+                        Ok(inserted)
+                    }
+                    Err(e) => {
+                        let _ = tx.rollback().await;
+                        Err(e)
+                    }
+                }
+            })
+            .await
+    }
+
+    // Fetches one page of the query's rows, by offset or keyset (see PageRequest)
+    pub async fn paginate<T, P>(&self, query: &str, params: P, request: &PageRequest) -> Result<Page<T>>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+        P: IntoIterator,
+        P::Item: Into<Value>,
     {
-        let conn = self.connection()?;
-        let result = sqlx::query_as::<_, T>(query).fetch_optional(conn.pool()).await?;
-        Ok(result)
+        let values: Vec<Value> = params.into_iter().map(Into::into).collect();
+        pagination::paginate(self, query, values, request).await
+    }
+
+    pub(crate) fn cursor_key(&self) -> &[u8] {
+        self.config.cursor_key()
+    }
+
+    pub fn repository<E: Entity>(&self) -> Repository<'_, E> {
+        Repository::new(self)
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        self.config.retry()
+    }
+
+    executor::query_helpers!(on_pool);
+}
+
+// Commits if `f` returns Ok and rolls back on Err
+async fn run_in_transaction<F, R>(mut tx: Transaction, f: F) -> Result<R>
+where
+    F: for<'t> FnOnce(&'t mut Transaction) -> BoxFuture<'t, Result<R>>,
+{
+    match f(&mut tx).await {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(e) => {
+            // The closure's error is more useful than a failed rollback's
+            let _ = tx.rollback().await;
+            Err(e)
+        }
     }
 }
 
@@ -151,12 +316,22 @@ pub async fn create_default_database() -> Result<Database> {
     Database::new().await
 }
 
+// Convenience function for creating a private in-memory database
+pub async fn create_in_memory_database() -> Result<Database> {
+    DatabaseBuilder::new().in_memory().build().await
+}
+
 // Platform detection and default path resolution functions
 pub fn is_windows() -> bool {
     cfg!(target_os = "windows")
 }
 
 pub fn get_default_database_path() -> Result<std::path::PathBuf> {
+    Ok(get_default_database_dir()?.join("data.db"))
+}
+
+// The platform default BurnCloud directory, ignoring any environment overrides
+pub fn get_default_database_dir() -> Result<std::path::PathBuf> {
     let db_dir = if is_windows() {
         // Windows: %USERPROFILE%\AppData\Local\BurnCloud
         let user_profile = std::env::var("USERPROFILE")
@@ -165,17 +340,31 @@ pub fn get_default_database_path() -> Result<std::path::PathBuf> {
             .join("AppData")
             .join("Local")
             .join("BurnCloud")
+    } else if cfg!(target_os = "linux") {
+        // Linux: $XDG_DATA_HOME/burncloud, falling back to ~/.local/share/burncloud
+        dirs::data_dir()
+            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
+            .join("burncloud")
     } else {
-        // Linux: ~/.burncloud
+        // Other Unix: ~/.burncloud
         dirs::home_dir()
             .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
             .join(".burncloud")
     };
 
-    Ok(db_dir.join("data.db"))
+    Ok(db_dir)
 }
 
-fn create_directory_if_not_exists(path: &std::path::Path) -> Result<()> {
+// Where Linux builds kept the database before XDG support: ~/.burncloud/data.db
+pub fn get_legacy_database_path() -> Option<std::path::PathBuf> {
+    if cfg!(target_os = "linux") {
+        dirs::home_dir().map(|home| home.join(".burncloud").join("data.db"))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn create_directory_if_not_exists(path: &std::path::Path) -> Result<()> {
     if let Some(parent) = path.parent() {
         if !parent.exists() {
             std::fs::create_dir_all(parent)
@@ -184,4 +373,3 @@ fn create_directory_if_not_exists(path: &std::path::Path) -> Result<()> {
     }
     Ok(())
 }
-
@@ -0,0 +1,206 @@
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{DeserializeOwned, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserializer};
+use sqlx::sqlite::{SqliteRow, SqliteValueRef};
+use sqlx::{Column as _, Row, TypeInfo, ValueRef};
+
+use crate::error::Result;
+use crate::json::decode;
+
+/// Deserializes a row into any `DeserializeOwned` type, matching columns to fields by name.
+///
+/// Besides the usual scalar conversions, TEXT columns holding JSON deserialize into nested
+/// structs, maps and sequences, BLOBs into `Vec<u8>`, and integers into `bool`. Tuples are
+/// filled from columns by position. Failures surface as `DatabaseError::Serialization`.
+pub fn from_row<T: DeserializeOwned>(row: &SqliteRow) -> Result<T> {
+    let mut columns = Vec::with_capacity(row.len());
+    for (index, column) in row.columns().iter().enumerate() {
+        columns.push((column.name().to_string(), Column::read(row.try_get_raw(index)?)?));
+    }
+    Ok(T::deserialize(RowDeserializer { columns })?)
+}
+
+pub fn from_rows<T: DeserializeOwned>(rows: &[SqliteRow]) -> Result<Vec<T>> {
+    rows.iter().map(from_row).collect()
+}
+
+/// Deserializes the column named `column`, with the same conversions as [`from_row`].
+pub fn from_column<T: DeserializeOwned>(row: &SqliteRow, column: &str) -> Result<T> {
+    Ok(T::deserialize(Column::read(row.try_get_raw(column)?)?)?)
+}
+
+/// Like [`from_column`], but always parses TEXT as JSON, so a JSON string (`"ready"`) reads
+/// into a `String` or unit enum variant without its quotes.
+pub fn from_json_column<T: DeserializeOwned>(row: &SqliteRow, column: &str) -> Result<T> {
+    match Column::read(row.try_get_raw(column)?)? {
+        Column::Text(text) => Ok(serde_json::from_str(&text)?),
+        other => Ok(T::deserialize(other)?),
+    }
+}
+
+struct RowDeserializer {
+    columns: Vec<(String, Column)>,
+}
+
+impl<'de> Deserializer<'de> for RowDeserializer {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        visitor.visit_map(MapDeserializer::new(self.columns.into_iter()))
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        visitor.visit_seq(SeqDeserializer::new(self.columns.into_iter().map(|(_, column)| column)))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct map struct enum identifier ignored_any
+    }
+}
+
+// One column value in its SQLite storage class
+enum Column {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Column {
+    fn read(value: SqliteValueRef<'_>) -> Result<Self> {
+        if value.is_null() {
+            return Ok(Column::Null);
+        }
+        let column = match value.type_info().name() {
+            "INTEGER" => Column::Integer(decode(value)?),
+            "REAL" => Column::Real(decode(value)?),
+            "BLOB" => Column::Blob(decode(value)?),
+            _ => Column::Text(decode(value)?),
+        };
+        Ok(column)
+    }
+
+    // Structured targets read JSON out of TEXT columns
+    fn into_json(self) -> std::result::Result<serde_json::Value, serde_json::Error> {
+        match self {
+            Column::Text(text) => serde_json::from_str(&text),
+            Column::Null => Ok(serde_json::Value::Null),
+            Column::Integer(value) => Ok(value.into()),
+            Column::Real(value) => Ok(value.into()),
+            Column::Blob(bytes) => Ok(bytes.into()),
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for Column {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Column {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        match self {
+            Column::Null => visitor.visit_unit(),
+            Column::Integer(value) => visitor.visit_i64(value),
+            Column::Real(value) => visitor.visit_f64(value),
+            Column::Text(text) => visitor.visit_string(text),
+            Column::Blob(bytes) => visitor.visit_byte_buf(bytes),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        match self {
+            Column::Integer(value) => visitor.visit_bool(value != 0),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        match self {
+            Column::Null => visitor.visit_none(),
+            other => visitor.visit_some(other),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        match self {
+            // Vec<u8> asks for a sequence
+            Column::Blob(bytes) => visitor.visit_seq(SeqDeserializer::new(bytes.into_iter())),
+            other => other.into_json()?.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        self.into_json()?.deserialize_map(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        self.into_json()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        // Unit variants are stored as plain text, variants with data as JSON objects
+        let value = match self {
+            Column::Text(text) if text.trim_start().starts_with('{') => serde_json::from_str(&text)?,
+            Column::Text(text) => serde_json::Value::String(text),
+            other => other.into_json()?,
+        };
+        value.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct identifier ignored_any
+    }
+}
@@ -1,5 +1,9 @@
+use std::fmt;
+
 use thiserror::Error;
 
+use crate::value::Value;
+
 #[derive(Error, Debug)]
 pub enum DatabaseError {
     #[error("Database connection error: {0}")]
@@ -8,8 +12,20 @@ pub enum DatabaseError {
     #[error("Migration error: {0}")]
     Migration(String),
 
-    #[error("Query error: {0}")]
-    Query(String),
+    #[error("Query error: {source} ({context})")]
+    Query {
+        #[source]
+        source: sqlx::Error,
+        context: Box<QueryContext>,
+    },
+
+    #[error("Script statement {statement} (line {line}) failed: {source}")]
+    Script {
+        statement: usize,
+        line: usize,
+        #[source]
+        source: Box<DatabaseError>,
+    },
 
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -23,6 +39,9 @@ pub enum DatabaseError {
     #[error("Failed to create database directory: {0}")]
     DirectoryCreation(String),
 
+    #[error("Invalid configuration: {0}")]
+    Configuration(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -30,4 +49,191 @@ pub enum DatabaseError {
     InvalidData { message: String },
 }
 
-pub type Result<T> = std::result::Result<T, DatabaseError>;
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, DatabaseError>;
+
+/// The statement behind a failed query, safe to log.
+///
+/// The SQL has its whitespace collapsed and is truncated to [`QueryContext::MAX_SQL_LEN`]
+/// characters. Parameters are summarized by type and size only (`text(12 chars)`), never by
+/// value, so credentials and user data stay out of logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryContext {
+    sql: String,
+    params: Vec<String>,
+}
+
+impl QueryContext {
+    pub const MAX_SQL_LEN: usize = 200;
+
+    pub(crate) fn new(sql: &str, params: &[Value]) -> Self {
+        let collapsed = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+        let sql = match collapsed.char_indices().nth(Self::MAX_SQL_LEN) {
+            Some((end, _)) => format!("{}...", &collapsed[..end]),
+            None => collapsed,
+        };
+        Self {
+            sql,
+            params: params.iter().map(Value::redacted).collect(),
+        }
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+}
+
+impl fmt::Display for QueryContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sql: {}", self.sql)?;
+        if !self.params.is_empty() {
+            write!(f, "; params: [{}]", self.params.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// What went wrong, independent of how the error is wrapped.
+///
+/// Derived from SQLite's extended result codes, so callers can map errors (e.g. a duplicate
+/// name to HTTP 409) without matching on messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    UniqueViolation,
+    ForeignKeyViolation,
+    NotNullViolation,
+    CheckViolation,
+    NotFound,
+    Busy,
+    Locked,
+    ReadOnly,
+    Corrupt,
+    DiskFull,
+    Syntax,
+    Other,
+}
+
+/// The table, columns or constraint named in a constraint failure message.
+///
+/// SQLite reports columns for UNIQUE, PRIMARY KEY and NOT NULL failures, the constraint
+/// name for named CHECK constraints and the index name for unique expression indexes.
+/// FOREIGN KEY failures carry no details.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConstraintViolation {
+    pub table: Option<String>,
+    pub columns: Vec<String>,
+    pub constraint: Option<String>,
+}
+
+// Extended result codes, see https://www.sqlite.org/rescode.html
+const SQLITE_CONSTRAINT_CHECK: i32 = 275;
+const SQLITE_CONSTRAINT_FOREIGNKEY: i32 = 787;
+const SQLITE_CONSTRAINT_NOTNULL: i32 = 1299;
+const SQLITE_CONSTRAINT_PRIMARYKEY: i32 = 1555;
+const SQLITE_CONSTRAINT_UNIQUE: i32 = 2067;
+
+impl DatabaseError {
+    pub fn kind(&self) -> ErrorKind {
+        match self.sqlite_error() {
+            Some((code, message)) => classify(code, message),
+            None if matches!(self.sqlx_error(), Some(sqlx::Error::RowNotFound)) => ErrorKind::NotFound,
+            None => ErrorKind::Other,
+        }
+    }
+
+    /// Whether the operation may succeed if simply tried again (busy or locked database).
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.kind(), ErrorKind::Busy | ErrorKind::Locked)
+    }
+
+    /// The SQLite extended result code, if this error came from SQLite.
+    pub fn sqlite_code(&self) -> Option<i32> {
+        self.sqlite_error().map(|(code, _)| code)
+    }
+
+    /// Details of a UNIQUE, NOT NULL, CHECK or FOREIGN KEY violation.
+    pub fn constraint_violation(&self) -> Option<ConstraintViolation> {
+        match self.kind() {
+            ErrorKind::UniqueViolation
+            | ErrorKind::ForeignKeyViolation
+            | ErrorKind::NotNullViolation
+            | ErrorKind::CheckViolation => {}
+            _ => return None,
+        }
+        let (_, message) = self.sqlite_error()?;
+        Some(parse_constraint(message))
+    }
+
+    /// The SQL and parameter summary of a failed query.
+    pub fn query_context(&self) -> Option<&QueryContext> {
+        match self {
+            DatabaseError::Query { context, .. } => Some(context),
+            DatabaseError::Script { source, .. } => source.query_context(),
+            _ => None,
+        }
+    }
+
+    fn sqlx_error(&self) -> Option<&sqlx::Error> {
+        match self {
+            DatabaseError::Connection(e) | DatabaseError::Query { source: e, .. } => Some(e),
+            DatabaseError::Script { source, .. } => source.sqlx_error(),
+            _ => None,
+        }
+    }
+
+    fn sqlite_error(&self) -> Option<(i32, &str)> {
+        let Some(sqlx::Error::Database(e)) = self.sqlx_error() else {
+            return None;
+        };
+        let code = e.code()?.parse().ok()?;
+        Some((code, e.message()))
+    }
+}
+
+fn classify(code: i32, message: &str) -> ErrorKind {
+    match code {
+        SQLITE_CONSTRAINT_UNIQUE | SQLITE_CONSTRAINT_PRIMARYKEY => return ErrorKind::UniqueViolation,
+        SQLITE_CONSTRAINT_FOREIGNKEY => return ErrorKind::ForeignKeyViolation,
+        SQLITE_CONSTRAINT_NOTNULL => return ErrorKind::NotNullViolation,
+        SQLITE_CONSTRAINT_CHECK => return ErrorKind::CheckViolation,
+        _ => {}
+    }
+    // Primary result codes live in the low byte
+    match code & 0xff {
+        1 if message.contains("syntax error") || message.contains("incomplete input") => ErrorKind::Syntax,
+        5 => ErrorKind::Busy,
+        6 => ErrorKind::Locked,
+        8 => ErrorKind::ReadOnly,
+        11 | 26 => ErrorKind::Corrupt,
+        13 => ErrorKind::DiskFull,
+        _ => ErrorKind::Other,
+    }
+}
+
+// Parses messages such as "UNIQUE constraint failed: api_tokens.name, api_tokens.owner",
+// "CHECK constraint failed: positive_price" or "UNIQUE constraint failed: index 'idx_lower_name'"
+fn parse_constraint(message: &str) -> ConstraintViolation {
+    let mut violation = ConstraintViolation::default();
+    let Some((_, detail)) = message.split_once("constraint failed: ") else {
+        return violation;
+    };
+
+    if let Some(index) = detail.strip_prefix("index ") {
+        violation.constraint = Some(index.trim_matches('\'').to_string());
+        return violation;
+    }
+
+    for item in detail.split(", ") {
+        match item.split_once('.') {
+            Some((table, column)) => {
+                violation.table.get_or_insert_with(|| table.to_string());
+                violation.columns.push(column.to_string());
+            }
+            None => violation.constraint = Some(item.to_string()),
+        }
+    }
+    violation
+}
\ No newline at end of file
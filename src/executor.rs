@@ -0,0 +1,452 @@
+use futures::stream::{BoxStream, StreamExt};
+use sqlx::sqlite::{SqliteQueryResult, SqliteRow};
+use sqlx::{Executor, FromRow, Sqlite};
+
+use crate::error::{DatabaseError, QueryContext, Result};
+use crate::value::{into_arguments, Value};
+
+// Query helpers shared by Database (running on the pool) and Transaction (running on its
+// connection). `None` params behave exactly like `sqlx::query(sql)` without binds. Failures
+// are wrapped in DatabaseError::Query together with the SQL and a redacted param summary.
+
+fn query_error(source: sqlx::Error, query: &str, params: Option<&[Value]>) -> DatabaseError {
+    DatabaseError::Query {
+        source,
+        context: Box::new(QueryContext::new(query, params.unwrap_or_default())),
+    }
+}
+
+pub(crate) async fn execute<'c, E>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<SqliteQueryResult>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let result = match params {
+        Some(values) => sqlx::query_with(query, into_arguments(values.iter().cloned())).execute(executor).await,
+        None => sqlx::query(query).execute(executor).await,
+    };
+    result.map_err(|e| query_error(e, query, params))
+}
+
+pub(crate) async fn fetch_rows<'c, E>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<Vec<SqliteRow>>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let rows = match params {
+        Some(values) => sqlx::query_with(query, into_arguments(values.iter().cloned())).fetch_all(executor).await,
+        None => sqlx::query(query).fetch_all(executor).await,
+    };
+    rows.map_err(|e| query_error(e, query, params))
+}
+
+pub(crate) async fn fetch_one<'c, E, T>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<T>
+where
+    E: Executor<'c, Database = Sqlite>,
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let result = match params {
+        Some(values) => sqlx::query_as_with::<_, T, _>(query, into_arguments(values.iter().cloned())).fetch_one(executor).await,
+        None => sqlx::query_as::<_, T>(query).fetch_one(executor).await,
+    };
+    result.map_err(|e| query_error(e, query, params))
+}
+
+pub(crate) async fn fetch_all<'c, E, T>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<Vec<T>>
+where
+    E: Executor<'c, Database = Sqlite>,
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let results = match params {
+        Some(values) => sqlx::query_as_with::<_, T, _>(query, into_arguments(values.iter().cloned())).fetch_all(executor).await,
+        None => sqlx::query_as::<_, T>(query).fetch_all(executor).await,
+    };
+    results.map_err(|e| query_error(e, query, params))
+}
+
+pub(crate) async fn fetch_optional<'c, E, T>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<Option<T>>
+where
+    E: Executor<'c, Database = Sqlite>,
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let result = match params {
+        Some(values) => sqlx::query_as_with::<_, T, _>(query, into_arguments(values.iter().cloned())).fetch_optional(executor).await,
+        None => sqlx::query_as::<_, T>(query).fetch_optional(executor).await,
+    };
+    result.map_err(|e| query_error(e, query, params))
+}
+
+pub(crate) async fn fetch_scalar<'c, E, T>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<T>
+where
+    E: Executor<'c, Database = Sqlite>,
+    T: for<'r> sqlx::Decode<'r, Sqlite> + sqlx::Type<Sqlite> + Send + Unpin,
+{
+    let result = match params {
+        Some(values) => sqlx::query_scalar_with::<_, T, _>(query, into_arguments(values.iter().cloned())).fetch_one(executor).await,
+        None => sqlx::query_scalar::<_, T>(query).fetch_one(executor).await,
+    };
+    result.map_err(|e| query_error(e, query, params))
+}
+
+pub(crate) async fn fetch_optional_scalar<'c, E, T>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<Option<T>>
+where
+    E: Executor<'c, Database = Sqlite>,
+    T: for<'r> sqlx::Decode<'r, Sqlite> + sqlx::Type<Sqlite> + Send + Unpin,
+{
+    let result = match params {
+        Some(values) => sqlx::query_scalar_with::<_, T, _>(query, into_arguments(values.iter().cloned())).fetch_optional(executor).await,
+        None => sqlx::query_scalar::<_, T>(query).fetch_optional(executor).await,
+    };
+    result.map_err(|e| query_error(e, query, params))
+}
+
+pub(crate) async fn fetch_optional_row<'c, E>(
+    executor: E,
+    query: &str,
+    params: Option<&[Value]>,
+) -> Result<Option<SqliteRow>>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    let row = match params {
+        Some(values) => sqlx::query_with(query, into_arguments(values.iter().cloned())).fetch_optional(executor).await,
+        None => sqlx::query(query).fetch_optional(executor).await,
+    };
+    row.map_err(|e| query_error(e, query, params))
+}
+
+// Like fetch_optional_row, with a missing row reported as RowNotFound for this query
+pub(crate) async fn fetch_one_row<'c, E>(executor: E, query: &str, params: Option<&[Value]>) -> Result<SqliteRow>
+where
+    E: Executor<'c, Database = Sqlite>,
+{
+    fetch_optional_row(executor, query, params)
+        .await?
+        .ok_or_else(|| query_error(sqlx::Error::RowNotFound, query, params))
+}
+
+// Rows are decoded one at a time as the caller polls; nothing is buffered beyond what SQLite
+// itself holds for the current step
+pub(crate) fn fetch_stream<'e, E, T>(executor: E, query: &'e str, params: Vec<Value>) -> BoxStream<'e, Result<T>>
+where
+    E: Executor<'e, Database = Sqlite> + 'e,
+    T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin + 'e,
+{
+    let arguments = into_arguments(params.iter().cloned());
+    sqlx::query_as_with::<_, T, _>(query, arguments)
+        .fetch(executor)
+        .map(move |row| row.map_err(|e| query_error(e, query, Some(&params))))
+        .boxed()
+}
+
+// Generates the query helpers of Database and Transaction, which only differ in how they
+// reach an executor. `$with!(self, |exec| future)` runs the future on an executor (retried
+// by Database, on the open connection by Transaction) and `$with!(stream self, |exec| ..)`
+// just binds one; `mut` makes the methods take `&mut self`.
+macro_rules! query_helpers {
+    ($with:ident $(, $mutability:tt)?) => {
+        // Inserts rows of one value per column as a single batch; returns the rows inserted
+        pub async fn insert_many<I, R>(&$($mutability)? self, table: &str, columns: &[&str], rows: I) -> $crate::error::Result<u64>
+        where
+            I: IntoIterator<Item = R>,
+            R: IntoIterator,
+            R::Item: Into<$crate::value::Value>,
+        {
+            self.insert_many_with($crate::batch::OnConflict::Abort, table, columns, rows).await
+        }
+
+        pub async fn insert_many_with<I, R>(
+            &$($mutability)? self,
+            on_conflict: $crate::batch::OnConflict,
+            table: &str,
+            columns: &[&str],
+            rows: I,
+        ) -> $crate::error::Result<u64>
+        where
+            I: IntoIterator<Item = R>,
+            R: IntoIterator,
+            R::Item: Into<$crate::value::Value>,
+        {
+            self.run_batch($crate::batch::Batch::new(on_conflict, table, columns, rows)?).await
+        }
+
+        // Like insert_many, taking the columns from the fields of serializable records
+        pub async fn insert_records<I>(&$($mutability)? self, table: &str, records: I) -> $crate::error::Result<u64>
+        where
+            I: IntoIterator,
+            I::Item: serde::Serialize,
+        {
+            self.insert_records_with($crate::batch::OnConflict::Abort, table, records).await
+        }
+
+        pub async fn insert_records_with<I>(
+            &$($mutability)? self,
+            on_conflict: $crate::batch::OnConflict,
+            table: &str,
+            records: I,
+        ) -> $crate::error::Result<u64>
+        where
+            I: IntoIterator,
+            I::Item: serde::Serialize,
+        {
+            self.run_batch($crate::batch::Batch::from_records(on_conflict, table, records)?).await
+        }
+
+        // Inserts `record` or merges it into the row it conflicts with, column by column per `policy`
+        pub async fn upsert<T>(
+            &$($mutability)? self,
+            table: &str,
+            record: &T,
+            conflict_columns: &[&str],
+            policy: &$crate::upsert::UpdatePolicy,
+        ) -> $crate::error::Result<u64>
+        where
+            T: serde::Serialize + ?Sized,
+        {
+            let (sql, values) = $crate::upsert::upsert_sql(table, record, conflict_columns, policy)?;
+            Ok(self.execute_query_with_params(&sql, values).await?.rows_affected())
+        }
+
+        pub async fn execute_query(&$($mutability)? self, query: &str) -> $crate::error::Result<sqlx::sqlite::SqliteQueryResult> {
+            $with!(self, |exec| $crate::executor::execute(exec, query, None))
+        }
+
+        pub async fn execute_query_with_params<P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<sqlx::sqlite::SqliteQueryResult>
+        where
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            $with!(self, |exec| $crate::executor::execute(exec, query, Some(&values)))
+        }
+
+        pub async fn query(&$($mutability)? self, query: &str) -> $crate::error::Result<Vec<sqlx::sqlite::SqliteRow>> {
+            $with!(self, |exec| $crate::executor::fetch_rows(exec, query, None))
+        }
+
+        pub async fn query_with_params<P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Vec<sqlx::sqlite::SqliteRow>>
+        where
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            $with!(self, |exec| $crate::executor::fetch_rows(exec, query, Some(&values)))
+        }
+
+        pub async fn fetch_one<T>(&$($mutability)? self, query: &str) -> $crate::error::Result<T>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+        {
+            $with!(self, |exec| $crate::executor::fetch_one(exec, query, None))
+        }
+
+        pub async fn fetch_all<T>(&$($mutability)? self, query: &str) -> $crate::error::Result<Vec<T>>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+        {
+            $with!(self, |exec| $crate::executor::fetch_all(exec, query, None))
+        }
+
+        pub async fn fetch_optional<T>(&$($mutability)? self, query: &str) -> $crate::error::Result<Option<T>>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+        {
+            $with!(self, |exec| $crate::executor::fetch_optional(exec, query, None))
+        }
+
+        pub async fn fetch_one_with_params<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<T>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            $with!(self, |exec| $crate::executor::fetch_one(exec, query, Some(&values)))
+        }
+
+        pub async fn fetch_all_with_params<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Vec<T>>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            $with!(self, |exec| $crate::executor::fetch_all(exec, query, Some(&values)))
+        }
+
+        pub async fn fetch_optional_with_params<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Option<T>>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            $with!(self, |exec| $crate::executor::fetch_optional(exec, query, Some(&values)))
+        }
+
+        pub async fn fetch_scalar<T>(&$($mutability)? self, query: &str) -> $crate::error::Result<T>
+        where
+            T: for<'r> sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + Unpin,
+        {
+            $with!(self, |exec| $crate::executor::fetch_scalar(exec, query, None))
+        }
+
+        pub async fn fetch_scalar_with_params<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<T>
+        where
+            T: for<'r> sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + Unpin,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            $with!(self, |exec| $crate::executor::fetch_scalar(exec, query, Some(&values)))
+        }
+
+        pub async fn fetch_optional_scalar<T>(&$($mutability)? self, query: &str) -> $crate::error::Result<Option<T>>
+        where
+            T: for<'r> sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + Unpin,
+        {
+            $with!(self, |exec| $crate::executor::fetch_optional_scalar(exec, query, None))
+        }
+
+        pub async fn fetch_optional_scalar_with_params<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Option<T>>
+        where
+            T: for<'r> sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Send + Unpin,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            $with!(self, |exec| $crate::executor::fetch_optional_scalar(exec, query, Some(&values)))
+        }
+
+        // Yields typed rows as they are read instead of collecting them into a Vec, for exports
+        // over result sets too large to hold in memory. Streams are not retried, and the
+        // connection stays checked out until the stream is dropped.
+        pub fn stream<'a, T, P>(&'a $($mutability)? self, query: &'a str, params: P) -> futures::stream::BoxStream<'a, $crate::error::Result<T>>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin + 'a,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            use futures::stream::{StreamExt, TryStreamExt};
+
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            let rows = async move {
+                Ok::<_, $crate::error::DatabaseError>($with!(stream self, |exec| $crate::executor::fetch_stream(exec, query, values)))
+            };
+            futures::stream::once(rows).try_flatten().boxed()
+        }
+
+        // Deserializes rows with serde instead of sqlx::FromRow; see de::from_row
+        pub async fn fetch_one_as<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<T>
+        where
+            T: serde::de::DeserializeOwned,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            let row = $with!(self, |exec| $crate::executor::fetch_one_row(exec, query, Some(&values)))?;
+            $crate::de::from_row(&row)
+        }
+
+        pub async fn fetch_all_as<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Vec<T>>
+        where
+            T: serde::de::DeserializeOwned,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let rows = self.query_with_params(query, params).await?;
+            $crate::de::from_rows(&rows)
+        }
+
+        pub async fn fetch_optional_as<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Option<T>>
+        where
+            T: serde::de::DeserializeOwned,
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let values: Vec<$crate::value::Value> = params.into_iter().map(Into::into).collect();
+            let row = $with!(self, |exec| $crate::executor::fetch_optional_row(exec, query, Some(&values)))?;
+            row.as_ref().map($crate::de::from_row).transpose()
+        }
+
+        pub async fn query_json<P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Vec<serde_json::Value>>
+        where
+            P: IntoIterator,
+            P::Item: Into<$crate::value::Value>,
+        {
+            let rows = self.query_with_params(query, params).await?;
+            $crate::json::rows_to_json(&rows)
+        }
+
+        pub async fn execute_named<P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<sqlx::sqlite::SqliteQueryResult>
+        where
+            P: $crate::named::IntoNamedParams,
+        {
+            let (query, values) = params.into_named_params()?.resolve(query)?;
+            self.execute_query_with_params(&query, values).await
+        }
+
+        pub async fn query_named<P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Vec<sqlx::sqlite::SqliteRow>>
+        where
+            P: $crate::named::IntoNamedParams,
+        {
+            let (query, values) = params.into_named_params()?.resolve(query)?;
+            self.query_with_params(&query, values).await
+        }
+
+        pub async fn fetch_one_named<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<T>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+            P: $crate::named::IntoNamedParams,
+        {
+            let (query, values) = params.into_named_params()?.resolve(query)?;
+            self.fetch_one_with_params(&query, values).await
+        }
+
+        pub async fn fetch_all_named<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Vec<T>>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+            P: $crate::named::IntoNamedParams,
+        {
+            let (query, values) = params.into_named_params()?.resolve(query)?;
+            self.fetch_all_with_params(&query, values).await
+        }
+
+        pub async fn fetch_optional_named<T, P>(&$($mutability)? self, query: &str, params: P) -> $crate::error::Result<Option<T>>
+        where
+            T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+            P: $crate::named::IntoNamedParams,
+        {
+            let (query, values) = params.into_named_params()?.resolve(query)?;
+            self.fetch_optional_with_params(&query, values).await
+        }
+    };
+}
+
+pub(crate) use query_helpers;
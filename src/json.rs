@@ -0,0 +1,153 @@
+use std::ops::Index;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+use serde_json::{Map, Number, Value as JsonValue};
+use sqlx::sqlite::{SqliteRow, SqliteValueRef};
+use sqlx::{Column, Decode, Row, Sqlite, TypeInfo, ValueRef};
+
+use crate::error::Result;
+
+/// A row as a JSON object that keeps the query's column order, for callers of
+/// [`row_to_json`] that need it.
+///
+/// It serializes as a JSON object with its columns in order, compares equal to the
+/// `serde_json::Value` object with the same entries, and indexes like one: `row["name"]` is
+/// `Value::Null` for a missing column. [`JsonRow::into_value`] converts it to a plain
+/// `serde_json::Value`, whose map does not keep the order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonRow {
+    columns: Vec<(String, JsonValue)>,
+}
+
+impl JsonRow {
+    /// Converts a row like [`row_to_json`], keeping its column order.
+    pub fn from_row(row: &SqliteRow) -> Result<Self> {
+        let mut object = Self {
+            columns: Vec::with_capacity(row.len()),
+        };
+        for (index, column) in row.columns().iter().enumerate() {
+            let value = row.try_get_raw(index)?;
+            let declared_bool = column.type_info().name() == "BOOLEAN";
+            object.insert(column.name().to_string(), value_to_json(value, declared_bool)?);
+        }
+        Ok(object)
+    }
+
+    pub fn get(&self, column: &str) -> Option<&JsonValue> {
+        self.columns.iter().find(|(name, _)| name == column).map(|(_, value)| value)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &JsonValue)> {
+        self.columns.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    pub fn into_value(self) -> JsonValue {
+        JsonValue::Object(self.columns.into_iter().collect::<Map<_, _>>())
+    }
+
+    // A repeated column name keeps its first position and its last value
+    fn insert(&mut self, column: String, value: JsonValue) {
+        match self.columns.iter_mut().find(|(name, _)| *name == column) {
+            Some((_, existing)) => *existing = value,
+            None => self.columns.push((column, value)),
+        }
+    }
+}
+
+impl Serialize for JsonRow {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (name, value) in &self.columns {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl Index<&str> for JsonRow {
+    type Output = JsonValue;
+
+    fn index(&self, column: &str) -> &JsonValue {
+        static NULL: JsonValue = JsonValue::Null;
+        self.get(column).unwrap_or(&NULL)
+    }
+}
+
+impl PartialEq<JsonValue> for JsonRow {
+    fn eq(&self, other: &JsonValue) -> bool {
+        match other.as_object() {
+            Some(object) => object.len() == self.len() && self.iter().all(|(name, value)| object.get(name) == Some(value)),
+            None => false,
+        }
+    }
+}
+
+impl From<JsonRow> for JsonValue {
+    fn from(row: JsonRow) -> Self {
+        row.into_value()
+    }
+}
+
+impl IntoIterator for JsonRow {
+    type Item = (String, JsonValue);
+    type IntoIter = std::vec::IntoIter<(String, JsonValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.columns.into_iter()
+    }
+}
+
+/// Converts a row into a JSON object keyed by column name.
+///
+/// Values keep the storage class SQLite returned for them: INTEGER becomes a JSON integer,
+/// REAL a JSON number (or `null` for NaN and infinities), TEXT a string and BLOB a base64
+/// string. Integers in a column declared `BOOLEAN` become `true`/`false`. When several
+/// columns share a name, the last one wins. The object's keys are sorted; use
+/// [`JsonRow::from_row`] to keep the query's column order.
+pub fn row_to_json(row: &SqliteRow) -> Result<JsonValue> {
+    JsonRow::from_row(row).map(JsonRow::into_value)
+}
+
+pub fn rows_to_json(rows: &[SqliteRow]) -> Result<Vec<JsonValue>> {
+    rows.iter().map(row_to_json).collect()
+}
+
+pub(crate) fn value_to_json(value: SqliteValueRef<'_>, declared_bool: bool) -> Result<JsonValue> {
+    if value.is_null() {
+        return Ok(JsonValue::Null);
+    }
+
+    let json = match value.type_info().name() {
+        "INTEGER" => {
+            let integer = decode::<i64>(value)?;
+            if declared_bool && (integer == 0 || integer == 1) {
+                JsonValue::Bool(integer == 1)
+            } else {
+                JsonValue::from(integer)
+            }
+        }
+        "REAL" => Number::from_f64(decode::<f64>(value)?).map_or(JsonValue::Null, JsonValue::Number),
+        "BLOB" => JsonValue::String(BASE64.encode(decode::<Vec<u8>>(value)?)),
+        _ => JsonValue::String(decode::<String>(value)?),
+    };
+    Ok(json)
+}
+
+pub(crate) fn decode<'r, T: Decode<'r, Sqlite>>(value: SqliteValueRef<'r>) -> Result<T> {
+    T::decode(value).map_err(|e| sqlx::Error::Decode(e).into())
+}
@@ -0,0 +1,72 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+
+use crate::database::create_directory_if_not_exists;
+use crate::error::Result;
+
+const BUSY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Copies a database from a legacy location to `target` and returns the path to open.
+///
+/// Nothing happens if there is no legacy database or `target` already exists. The copy is
+/// made with `VACUUM INTO`, which reads a consistent snapshot (including rows committed to
+/// the WAL) without locking other connections out, and only appears at `target` once it is
+/// complete. The legacy files are left untouched, so an older build that still has them
+/// open keeps working, but anything it writes after the copy stays in the legacy database.
+/// If the copy cannot be made, the legacy database is adopted in place and its path is
+/// returned instead.
+pub async fn relocate_legacy_database(legacy: &Path, target: &Path) -> Result<PathBuf> {
+    if legacy == target || !legacy.is_file() || target.exists() {
+        return Ok(target.to_path_buf());
+    }
+
+    if copy_database(legacy, target).await {
+        Ok(target.to_path_buf())
+    } else {
+        Ok(legacy.to_path_buf())
+    }
+}
+
+// The snapshot is written next to `to` and renamed into place, so an interrupted copy is
+// never mistaken for the database
+async fn copy_database(from: &Path, to: &Path) -> bool {
+    if create_directory_if_not_exists(to).is_err() {
+        return false;
+    }
+    let partial = with_suffix(to, "-partial");
+    let _ = std::fs::remove_file(&partial);
+
+    let copied = match SqliteConnectOptions::new()
+        .filename(from)
+        .create_if_missing(false)
+        // Rides out a writer that is just committing without stalling startup behind a
+        // long-running one
+        .busy_timeout(BUSY_TIMEOUT)
+        .connect()
+        .await
+    {
+        Ok(mut conn) => {
+            let copied = sqlx::query("VACUUM INTO ?")
+                .bind(partial.to_string_lossy().into_owned())
+                .execute(&mut conn)
+                .await;
+            let _ = conn.close().await;
+            copied.is_ok() && std::fs::rename(&partial, to).is_ok()
+        }
+        Err(_) => false,
+    };
+
+    if !copied {
+        let _ = std::fs::remove_file(&partial);
+    }
+    copied
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
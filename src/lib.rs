@@ -1,7 +1,45 @@
+pub mod batch;
+pub mod builder;
+pub mod config;
 pub mod database;
+pub mod de;
 pub mod error;
+mod executor;
+pub mod json;
+pub mod legacy;
+pub mod migration;
+pub mod named;
+pub mod pagination;
+pub mod pragma;
+pub mod query;
+pub mod repository;
+pub mod retry;
+pub mod script;
+mod ser;
+pub mod transaction;
+pub mod upsert;
+pub mod value;
 
-pub use database::{Database, DatabaseConnection, create_default_database, get_default_database_path, is_windows};
-pub use error::{DatabaseError, Result};
+pub use batch::{quote_identifier, OnConflict};
+pub use builder::DatabaseBuilder;
+pub use config::{DatabaseConfig, CONFIG_ENV, DATABASE_URL_ENV, HOME_ENV};
+pub use database::{Database, DatabaseConnection, create_default_database, create_in_memory_database, get_default_database_dir, get_default_database_path, get_legacy_database_path, is_windows};
+pub use json::{row_to_json, rows_to_json, JsonRow};
+pub use legacy::relocate_legacy_database;
+pub use de::{from_column, from_json_column, from_row, from_rows};
+pub use error::{ConstraintViolation, DatabaseError, ErrorKind, QueryContext, Result};
+pub use migration::{AppliedMigration, Migration, MigrationStep, Migrator};
+pub use named::{IntoNamedParams, NamedParams};
+pub use pagination::{Page, PageRequest};
+pub use pragma::{EffectivePragmas, PragmaProfile, TempStore};
+pub use query::{col, Column, Condition, Delete, Insert, Query, Select, Update};
+pub use repository::{Entity, Repository};
+#[cfg(feature = "derive")]
+pub use burncloud_database_derive::Entity;
+pub use retry::RetryPolicy;
+pub use script::split_statements;
+pub use transaction::{Transaction, TransactionBehavior};
+pub use upsert::{MergeRule, UpdatePolicy};
+pub use value::Value;
 
 pub use sqlx;
\ No newline at end of file
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqlitePool};
+
+use crate::database::DatabaseConnection;
+use crate::error::{DatabaseError, Result};
+use crate::transaction::{Transaction, TransactionBehavior};
+
+/// Name of the bookkeeping table that records applied migrations.
+pub const MIGRATIONS_TABLE: &str = "_burncloud_migrations";
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+type MigrationFn = Arc<dyn for<'c> Fn(&'c mut SqliteConnection) -> BoxFuture<'c, Result<()>> + Send + Sync>;
+
+/// The work performed by a migration: either embedded SQL or a Rust closure.
+#[derive(Clone)]
+pub enum MigrationStep {
+    Sql(String),
+    Code(MigrationFn),
+}
+
+impl fmt::Debug for MigrationStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationStep::Sql(sql) => f.debug_tuple("Sql").field(sql).finish(),
+            MigrationStep::Code(_) => f.write_str("Code(..)"),
+        }
+    }
+}
+
+impl MigrationStep {
+    /// SHA-256 of the SQL text, hex encoded. Code steps have no checksum.
+    pub fn checksum(&self) -> Option<String> {
+        match self {
+            MigrationStep::Sql(sql) => Some(checksum(sql)),
+            MigrationStep::Code(_) => None,
+        }
+    }
+
+    async fn run(&self, conn: &mut SqliteConnection) -> Result<()> {
+        match self {
+            MigrationStep::Sql(sql) => {
+                sqlx::raw_sql(sql).execute(&mut *conn).await?;
+                Ok(())
+            }
+            MigrationStep::Code(f) => f(conn).await,
+        }
+    }
+}
+
+/// A single numbered schema change.
+#[derive(Clone, Debug)]
+pub struct Migration {
+    version: i64,
+    name: String,
+    up: MigrationStep,
+    down: Option<MigrationStep>,
+}
+
+impl Migration {
+    /// A migration that runs one or more SQL statements.
+    pub fn sql(version: i64, name: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: MigrationStep::Sql(sql.into()),
+            down: None,
+        }
+    }
+
+    /// A migration implemented in Rust. The closure runs on the migration's transaction.
+    pub fn code<F>(version: i64, name: impl Into<String>, f: F) -> Self
+    where
+        F: for<'c> Fn(&'c mut SqliteConnection) -> BoxFuture<'c, Result<()>> + Send + Sync + 'static,
+    {
+        Self {
+            version,
+            name: name.into(),
+            up: MigrationStep::Code(Arc::new(f)),
+            down: None,
+        }
+    }
+
+    /// Makes the migration reversible with SQL that undoes it.
+    pub fn with_down_sql(mut self, sql: impl Into<String>) -> Self {
+        self.down = Some(MigrationStep::Sql(sql.into()));
+        self
+    }
+
+    /// Makes the migration reversible with a Rust closure that undoes it.
+    pub fn with_down_code<F>(mut self, f: F) -> Self
+    where
+        F: for<'c> Fn(&'c mut SqliteConnection) -> BoxFuture<'c, Result<()>> + Send + Sync + 'static,
+    {
+        self.down = Some(MigrationStep::Code(Arc::new(f)));
+        self
+    }
+
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn up(&self) -> &MigrationStep {
+        &self.up
+    }
+
+    pub fn down(&self) -> Option<&MigrationStep> {
+        self.down.as_ref()
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+
+    pub fn checksum(&self) -> Option<String> {
+        self.up.checksum()
+    }
+}
+
+/// A row of the migrations bookkeeping table.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub applied_at: DateTime<Utc>,
+    pub checksum: Option<String>,
+}
+
+/// An ordered set of migrations.
+#[derive(Clone, Debug, Default)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a migration, keeping the set ordered by version.
+    pub fn add(&mut self, migration: Migration) -> Result<()> {
+        if migration.version <= 0 {
+            return Err(DatabaseError::Migration(format!(
+                "migration '{}' has invalid version {}: versions must be positive",
+                migration.name, migration.version
+            )));
+        }
+
+        match self.migrations.binary_search_by_key(&migration.version, |m| m.version) {
+            Ok(index) => Err(DatabaseError::Migration(format!(
+                "duplicate migration version {}: '{}' conflicts with '{}'",
+                migration.version, migration.name, self.migrations[index].name
+            ))),
+            Err(index) => {
+                self.migrations.insert(index, migration);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// The highest registered version, if any.
+    pub fn latest_version(&self) -> Option<i64> {
+        self.migrations.last().map(|m| m.version)
+    }
+
+    /// Lists the migrations recorded in the bookkeeping table.
+    pub async fn applied(&self, connection: &DatabaseConnection) -> Result<Vec<AppliedMigration>> {
+        ensure_migrations_table(connection.pool()).await?;
+
+        let applied = sqlx::query_as::<_, AppliedMigration>(&format!(
+            "SELECT version, name, applied_at, checksum FROM {} ORDER BY version",
+            MIGRATIONS_TABLE
+        ))
+        .fetch_all(connection.pool())
+        .await?;
+
+        Ok(applied)
+    }
+
+    /// Applies all pending migrations in a single transaction and returns their versions.
+    pub async fn run(&self, connection: &DatabaseConnection) -> Result<Vec<i64>> {
+        self.run_to_target(connection, None).await
+    }
+
+    /// Moves the schema forward or backward to exactly `version`, where `0` means no
+    /// migrations applied. Returns the versions applied or reverted, in execution order.
+    pub async fn run_to(&self, connection: &DatabaseConnection, version: i64) -> Result<Vec<i64>> {
+        if version != 0 && !self.migrations.iter().any(|m| m.version == version) {
+            return Err(DatabaseError::Migration(format!(
+                "cannot migrate to version {}: no such migration is registered",
+                version
+            )));
+        }
+        self.run_to_target(connection, Some(version)).await
+    }
+
+    async fn run_to_target(&self, connection: &DatabaseConnection, target: Option<i64>) -> Result<Vec<i64>> {
+        ensure_migrations_table(connection.pool()).await?;
+
+        // IMMEDIATE so concurrent migrators queue on the write lock instead of failing with
+        // SQLITE_BUSY when their first read upgrades to a write
+        let mut tx = Transaction::begin(connection.pool().acquire().await?, TransactionBehavior::Immediate).await?;
+
+        // Roll back explicitly so the connection is clean before it returns to the pool
+        match self.apply(tx.connection()?, target).await {
+            Ok(versions) => {
+                tx.commit().await?;
+                Ok(versions)
+            }
+            Err(e) => {
+                let _ = tx.rollback().await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn apply(&self, conn: &mut SqliteConnection, target: Option<i64>) -> Result<Vec<i64>> {
+        let applied = sqlx::query_as::<_, AppliedMigration>(&format!(
+            "SELECT version, name, applied_at, checksum FROM {} ORDER BY version",
+            MIGRATIONS_TABLE
+        ))
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let pending = self.pending(&applied)?;
+
+        let current = applied.iter().map(|r| r.version).max().unwrap_or(0);
+        if let Some(target) = target.filter(|target| *target < current) {
+            return self.revert(conn, &applied, target).await;
+        }
+
+        let mut versions = Vec::with_capacity(pending.len());
+        for migration in pending.into_iter().filter(|m| target.is_none_or(|t| m.version <= t)) {
+            migration.up.run(conn).await.map_err(|e| {
+                DatabaseError::Migration(format!(
+                    "migration {} ('{}') failed: {}",
+                    migration.version, migration.name, e
+                ))
+            })?;
+
+            sqlx::query(&format!(
+                "INSERT INTO {} (version, name, applied_at, checksum) VALUES (?, ?, ?, ?)",
+                MIGRATIONS_TABLE
+            ))
+            .bind(migration.version)
+            .bind(&migration.name)
+            .bind(Utc::now())
+            .bind(migration.checksum())
+            .execute(&mut *conn)
+            .await?;
+
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+
+    // Runs down steps for every applied migration above `target`, newest first
+    async fn revert(&self, conn: &mut SqliteConnection, applied: &[AppliedMigration], target: i64) -> Result<Vec<i64>> {
+        let mut versions = Vec::new();
+        for record in applied.iter().rev().filter(|r| r.version > target) {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.version == record.version)
+                .ok_or_else(|| {
+                    DatabaseError::Migration(format!(
+                        "cannot revert migration {} ('{}'): it is not known to this build",
+                        record.version, record.name
+                    ))
+                })?;
+
+            let down = migration.down.as_ref().ok_or_else(|| {
+                DatabaseError::Migration(format!(
+                    "cannot revert migration {} ('{}'): it has no down step",
+                    migration.version, migration.name
+                ))
+            })?;
+
+            down.run(conn).await.map_err(|e| {
+                DatabaseError::Migration(format!(
+                    "reverting migration {} ('{}') failed: {}",
+                    migration.version, migration.name, e
+                ))
+            })?;
+
+            sqlx::query(&format!("DELETE FROM {} WHERE version = ?", MIGRATIONS_TABLE))
+                .bind(migration.version)
+                .execute(&mut *conn)
+                .await?;
+
+            versions.push(migration.version);
+        }
+        Ok(versions)
+    }
+
+    /// Checks the recorded history against the registered set without applying anything.
+    pub async fn verify(&self, connection: &DatabaseConnection) -> Result<()> {
+        let applied = self.applied(connection).await?;
+        self.pending(&applied)?;
+        Ok(())
+    }
+
+    // Checks the recorded history against the registered set and returns what is left to apply.
+    fn pending(&self, applied: &[AppliedMigration]) -> Result<Vec<&Migration>> {
+        let known: HashMap<i64, &Migration> = self.migrations.iter().map(|m| (m.version, m)).collect();
+
+        for record in applied {
+            match known.get(&record.version) {
+                None => {
+                    return Err(DatabaseError::Migration(format!(
+                        "database has migration {} ('{}') applied, but it is not known to this build",
+                        record.version, record.name
+                    )))
+                }
+                Some(migration) if migration.name != record.name => {
+                    return Err(DatabaseError::Migration(format!(
+                        "migration {} is recorded as '{}' but this build names it '{}'",
+                        record.version, record.name, migration.name
+                    )))
+                }
+                Some(migration) => {
+                    if let (Some(recorded), Some(current)) = (&record.checksum, migration.checksum()) {
+                        if *recorded != current {
+                            return Err(DatabaseError::Migration(format!(
+                                "migration {} ('{}') was modified after it was applied: recorded checksum {}, current checksum {}",
+                                record.version, record.name, recorded, current
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+
+        let latest_applied = applied.iter().map(|r| r.version).max();
+        let pending: Vec<&Migration> = self
+            .migrations
+            .iter()
+            .filter(|m| !applied.iter().any(|r| r.version == m.version))
+            .collect();
+
+        if let (Some(latest), Some(first)) = (latest_applied, pending.first()) {
+            if first.version < latest {
+                return Err(DatabaseError::Migration(format!(
+                    "migration {} ('{}') is pending but the database is already at version {}",
+                    first.version, first.name, latest
+                )));
+            }
+        }
+
+        Ok(pending)
+    }
+}
+
+async fn ensure_migrations_table(pool: &SqlitePool) -> Result<()> {
+    sqlx::query(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL,
+            checksum TEXT
+        )",
+        MIGRATIONS_TABLE
+    ))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn checksum(sql: &str) -> String {
+    Sha256::digest(sql.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,209 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::error::{DatabaseError, Result};
+use crate::value::Value;
+
+/// Bind parameters addressed by name (`:model_id`, `@model_id` or `$model_id` in SQL).
+///
+/// Names are stored without their prefix.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NamedParams {
+    values: HashMap<String, Value>,
+}
+
+impl NamedParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a parameter, builder style.
+    pub fn with(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        let name = name.into();
+        let name = name.trim_start_matches([':', '@', '$']).to_string();
+        self.values.insert(name, value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Uses the fields of a struct (or entries of a map) as parameters.
+    ///
+    /// Nested arrays and objects become [`Value::Json`]. Anything that does not serialize to
+    /// an object is rejected.
+    pub fn from_serialize<T: Serialize + ?Sized>(value: &T) -> Result<Self> {
+        match serde_json::to_value(value)? {
+            serde_json::Value::Object(fields) => Ok(fields
+                .into_iter()
+                .map(|(name, value)| (name, Value::from_json_field(value)))
+                .collect()),
+            other => Err(DatabaseError::InvalidData {
+                message: format!("named parameters must serialize to an object, got {}", other),
+            }),
+        }
+    }
+
+    /// Rewrites named placeholders in `sql` to numbered `?NNN` placeholders and returns the
+    /// rewritten SQL with the matching positional values.
+    ///
+    /// Placeholders inside string literals, quoted identifiers and comments are left alone.
+    /// A name used more than once binds a single value. Parameters not referenced by the SQL
+    /// are ignored, so a struct may carry extra fields.
+    pub fn resolve(&self, sql: &str) -> Result<(String, Vec<Value>)> {
+        let mut output = String::with_capacity(sql.len());
+        let mut order: Vec<&str> = Vec::new();
+        let mut values = Vec::new();
+
+        let chars: Vec<char> = sql.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            match c {
+                '\'' | '"' | '`' | '[' => {
+                    let close = if c == '[' { ']' } else { c };
+                    let end = find_closing(&chars, i + 1, close);
+                    output.extend(&chars[i..end]);
+                    i = end;
+                }
+                '-' if chars.get(i + 1) == Some(&'-') => {
+                    let end = chars[i..].iter().position(|&c| c == '\n').map_or(chars.len(), |p| i + p);
+                    output.extend(&chars[i..end]);
+                    i = end;
+                }
+                '/' if chars.get(i + 1) == Some(&'*') => {
+                    let end = (i + 2..chars.len().saturating_sub(1))
+                        .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                        .map_or(chars.len(), |j| j + 2);
+                    output.extend(&chars[i..end]);
+                    i = end;
+                }
+                '?' => {
+                    return Err(DatabaseError::InvalidData {
+                        message: "positional '?' placeholders cannot be mixed with named parameters".to_string(),
+                    });
+                }
+                ':' | '@' | '$' if chars.get(i + 1).is_some_and(|&c| c.is_ascii_alphabetic() || c == '_') => {
+                    let start = i + 1;
+                    let end = (start..chars.len())
+                        .find(|&j| !(chars[j].is_ascii_alphanumeric() || chars[j] == '_'))
+                        .unwrap_or(chars.len());
+                    let name: String = chars[start..end].iter().collect();
+
+                    let (key, value) = self.values.get_key_value(&name).ok_or_else(|| DatabaseError::InvalidData {
+                        message: format!("missing value for named parameter {}{}", c, name),
+                    })?;
+                    let index = match order.iter().position(|n| *n == key.as_str()) {
+                        Some(index) => index,
+                        None => {
+                            order.push(key.as_str());
+                            values.push(value.clone());
+                            order.len() - 1
+                        }
+                    };
+                    output.push('?');
+                    output.push_str(&(index + 1).to_string());
+                    i = end;
+                }
+                _ => {
+                    output.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        Ok((output, values))
+    }
+}
+
+// Index just past the closing quote, treating a doubled quote as an escaped one
+fn find_closing(chars: &[char], mut i: usize, close: char) -> usize {
+    while i < chars.len() {
+        if chars[i] == close {
+            if close != ']' && chars.get(i + 1) == Some(&close) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for NamedParams {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut params = NamedParams::new();
+        for (name, value) in iter {
+            params.insert(name, value);
+        }
+        params
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> From<HashMap<K, V>> for NamedParams {
+    fn from(map: HashMap<K, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<K: Into<String>, V: Into<Value>> From<BTreeMap<K, V>> for NamedParams {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+/// Anything usable as a set of named parameters: [`NamedParams`] or any `Serialize` struct or map.
+pub trait IntoNamedParams {
+    fn into_named_params(self) -> Result<NamedParams>;
+}
+
+impl IntoNamedParams for NamedParams {
+    fn into_named_params(self) -> Result<NamedParams> {
+        Ok(self)
+    }
+}
+
+impl IntoNamedParams for &NamedParams {
+    fn into_named_params(self) -> Result<NamedParams> {
+        Ok(self.clone())
+    }
+}
+
+impl<T: Serialize> IntoNamedParams for &T {
+    fn into_named_params(self) -> Result<NamedParams> {
+        NamedParams::from_serialize(self)
+    }
+}
+
+/// Builds [`NamedParams`] from `name => value` pairs.
+///
+/// ```
+/// use burncloud_database::{named_params, Value};
+///
+/// let params = named_params! { "model_id" => 42, "status" => "ready" };
+/// assert_eq!(params.get("model_id"), Some(&Value::Integer(42)));
+/// ```
+#[macro_export]
+macro_rules! named_params {
+    () => {
+        $crate::NamedParams::new()
+    };
+    ($($name:expr => $value:expr),+ $(,)?) => {
+        $crate::NamedParams::new()$(.with($name, $value))+
+    };
+}
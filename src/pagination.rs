@@ -0,0 +1,300 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, TypeInfo, ValueRef};
+
+use crate::batch::quote_identifier;
+use crate::database::Database;
+use crate::error::{DatabaseError, Result};
+use crate::json::value_to_json;
+use crate::value::Value;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// One page of results from [`Database::paginate`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The number of rows across all pages; only counted for offset pagination.
+    pub total: Option<i64>,
+    /// Passed to [`PageRequest::after`] to fetch the next page; only set for keyset
+    /// pagination when `has_more` is true.
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Converts the items, keeping the paging fields.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            next_cursor: self.next_cursor,
+            has_more: self.has_more,
+        }
+    }
+}
+
+/// Which page [`Database::paginate`] fetches.
+///
+/// Offset pagination appends `LIMIT`/`OFFSET` to the query, so the query should have a
+/// stable `ORDER BY` and no `LIMIT` of its own. It also counts the total, which scans
+/// every matching row.
+///
+/// Keyset pagination orders by `columns` itself and continues after the last row of the
+/// previous page, so pages stay consistent while rows are inserted and deep pages are as
+/// cheap as the first. The key columns must be selected by the query, must not be NULL or
+/// BLOB, and must be unique in combination; end with the primary key to break ties.
+/// Cursors are signed with the database's
+/// [`cursor_secret`](crate::DatabaseBuilder::cursor_secret), so clients cannot forge or
+/// edit them, and are only accepted for the same query, parameters and ordering.
+///
+/// **Set a `cursor_secret`**: the default is random per builder, so without one every
+/// cursor handed out becomes invalid when the process restarts.
+///
+/// ```
+/// use burncloud_database::PageRequest;
+///
+/// let first = PageRequest::page(1, 50);
+/// let newest = PageRequest::keyset(["created_at", "id"], 50).descending();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRequest {
+    mode: Mode,
+    limit: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Mode {
+    Offset(u64),
+    Keyset {
+        columns: Vec<String>,
+        descending: bool,
+        after: Option<String>,
+    },
+}
+
+impl PageRequest {
+    pub fn offset(offset: u64, limit: u64) -> Self {
+        Self {
+            mode: Mode::Offset(offset),
+            limit,
+        }
+    }
+
+    /// Page `page` of `per_page` rows, counting from 1.
+    pub fn page(page: u64, per_page: u64) -> Self {
+        Self::offset(page.saturating_sub(1).saturating_mul(per_page), per_page)
+    }
+
+    /// The first page of `limit` rows ordered by `columns`, ascending.
+    pub fn keyset<I>(columns: I, limit: u64) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            mode: Mode::Keyset {
+                columns: columns.into_iter().map(Into::into).collect(),
+                descending: false,
+                after: None,
+            },
+            limit,
+        }
+    }
+
+    /// Orders a keyset page by its columns descending. No effect on offset requests.
+    pub fn descending(mut self) -> Self {
+        if let Mode::Keyset { descending, .. } = &mut self.mode {
+            *descending = true;
+        }
+        self
+    }
+
+    /// Continues after the page that returned `cursor` as its `next_cursor`. No effect on
+    /// offset requests.
+    pub fn after(mut self, cursor: impl Into<String>) -> Self {
+        if let Mode::Keyset { after, .. } = &mut self.mode {
+            *after = Some(cursor.into());
+        }
+        self
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+pub(crate) async fn paginate<T>(db: &Database, query: &str, values: Vec<Value>, request: &PageRequest) -> Result<Page<T>>
+where
+    T: for<'r> sqlx::FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    if request.limit == 0 || request.limit > i64::MAX as u64 {
+        return Err(DatabaseError::InvalidData {
+            message: format!("page limit must be between 1 and {}, got {}", i64::MAX, request.limit),
+        });
+    }
+    let query = query.trim().trim_end_matches(';').trim_end();
+
+    match &request.mode {
+        Mode::Offset(offset) => offset_page(db, query, values, *offset, request.limit).await,
+        Mode::Keyset {
+            columns,
+            descending,
+            after,
+        } => {
+            let listing = Listing {
+                query,
+                values: &values,
+                columns,
+                descending: *descending,
+            };
+            keyset_page(db, &listing, after.as_deref(), request.limit).await
+        }
+    }
+}
+
+// Here and in keyset_page a newline follows the caller's SQL wherever more is appended, so
+// a trailing `--` comment cannot swallow it
+async fn offset_page<T>(db: &Database, query: &str, mut values: Vec<Value>, offset: u64, limit: u64) -> Result<Page<T>>
+where
+    T: for<'r> sqlx::FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    let total: i64 = db
+        .fetch_scalar_with_params(&format!("SELECT COUNT(*) FROM ({}\n)", query), values.clone())
+        .await?;
+    values.push(Value::Integer(limit as i64));
+    values.push(Value::Integer(offset.min(i64::MAX as u64) as i64));
+    let items: Vec<T> = db
+        .fetch_all_with_params(&format!("{}\nLIMIT ? OFFSET ?", query), values)
+        .await?;
+    let seen = offset.saturating_add(items.len() as u64);
+    Ok(Page {
+        items,
+        total: Some(total),
+        next_cursor: None,
+        has_more: seen < total.max(0) as u64,
+    })
+}
+
+// Everything a cursor is bound to: it only resumes the same query, with the same
+// parameters, in the same order
+struct Listing<'a> {
+    query: &'a str,
+    values: &'a [Value],
+    columns: &'a [String],
+    descending: bool,
+}
+
+async fn keyset_page<T>(db: &Database, listing: &Listing<'_>, after: Option<&str>, limit: u64) -> Result<Page<T>>
+where
+    T: for<'r> sqlx::FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    if listing.columns.is_empty() {
+        return Err(DatabaseError::InvalidData {
+            message: "keyset pagination needs at least one column".to_string(),
+        });
+    }
+
+    let key_list = listing.columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ");
+    let mut sql = format!("SELECT * FROM ({}\n)", listing.query);
+    let mut values = listing.values.to_vec();
+    if let Some(cursor) = after {
+        let key = decode_cursor(db.cursor_key(), listing, cursor)?;
+        let op = if listing.descending { "<" } else { ">" };
+        sql.push_str(&format!(" WHERE ({}) {} ({})", key_list, op, vec!["?"; key.len()].join(", ")));
+        values.extend(key);
+    }
+    let direction = if listing.descending { " DESC" } else { "" };
+    let order = listing
+        .columns
+        .iter()
+        .map(|c| format!("{}{}", quote_identifier(c), direction))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // One extra row tells whether another page follows
+    sql.push_str(&format!(" ORDER BY {} LIMIT {}", order, limit.saturating_add(1)));
+
+    let mut rows = db.query_with_params(&sql, values).await?;
+    let has_more = rows.len() as u64 > limit;
+    rows.truncate(limit as usize);
+    let next_cursor = match rows.last() {
+        Some(last) if has_more => Some(encode_cursor(db.cursor_key(), listing, last)?),
+        _ => None,
+    };
+    let items = rows.iter().map(T::from_row).collect::<std::result::Result<_, _>>()?;
+    Ok(Page {
+        items,
+        total: None,
+        next_cursor,
+        has_more,
+    })
+}
+
+// `base64(json key values).base64(hmac)`, where the MAC also covers the listing
+fn encode_cursor(secret: &[u8], listing: &Listing<'_>, row: &SqliteRow) -> Result<String> {
+    let mut key = Vec::with_capacity(listing.columns.len());
+    for column in listing.columns {
+        let value = row.try_get_raw(column.as_str())?;
+        if value.is_null() || value.type_info().name() == "BLOB" {
+            return Err(DatabaseError::InvalidData {
+                message: format!("keyset column {} must not be NULL or a BLOB", column),
+            });
+        }
+        key.push(value_to_json(value, false)?);
+    }
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&key)?);
+    let signature = URL_SAFE_NO_PAD.encode(cursor_mac(secret, listing, &payload).finalize().into_bytes());
+    Ok(format!("{}.{}", payload, signature))
+}
+
+fn decode_cursor(secret: &[u8], listing: &Listing<'_>, cursor: &str) -> Result<Vec<Value>> {
+    let invalid = || DatabaseError::InvalidData {
+        message: "invalid pagination cursor".to_string(),
+    };
+    let (payload, signature) = cursor.split_once('.').ok_or_else(invalid)?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+    cursor_mac(secret, listing, payload)
+        .verify_slice(&signature)
+        .map_err(|_| invalid())?;
+
+    let bytes = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+    let key: Vec<serde_json::Value> = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if key.len() != listing.columns.len() {
+        return Err(invalid());
+    }
+    Ok(key.into_iter().map(Value::from_json_field).collect())
+}
+
+fn cursor_mac(secret: &[u8], listing: &Listing<'_>, payload: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    // Every field is length-prefixed so no two listings feed the MAC the same bytes
+    let mut field = |tag: u8, bytes: &[u8]| {
+        mac.update(&[tag]);
+        mac.update(&(bytes.len() as u64).to_le_bytes());
+        mac.update(bytes);
+    };
+    field(b'q', listing.query.as_bytes());
+    for value in listing.values {
+        match value {
+            Value::Null => field(b'n', &[]),
+            Value::Integer(integer) => field(b'i', &integer.to_le_bytes()),
+            Value::Real(real) => field(b'r', &real.to_bits().to_le_bytes()),
+            Value::Text(text) => field(b't', text.as_bytes()),
+            Value::Blob(bytes) => field(b'b', bytes),
+            Value::Bool(flag) => field(b'o', &[*flag as u8]),
+            Value::Json(json) => field(b'j', json.to_string().as_bytes()),
+            Value::Uuid(uuid) => field(b'u', uuid.as_bytes()),
+            Value::DateTime(at) => field(b'd', at.to_rfc3339().as_bytes()),
+        }
+    }
+    for column in listing.columns {
+        field(b'c', column.as_bytes());
+    }
+    field(b'a', if listing.descending { b"desc" } else { b"asc" });
+    field(b'p', payload.as_bytes());
+    mac
+}
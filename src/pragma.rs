@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use sqlx::SqliteConnection;
+
+use crate::error::Result;
+
+/// Where SQLite keeps temporary tables and indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempStore {
+    Default,
+    File,
+    Memory,
+}
+
+impl TempStore {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TempStore::Default => "DEFAULT",
+            TempStore::File => "FILE",
+            TempStore::Memory => "MEMORY",
+        }
+    }
+}
+
+/// PRAGMA settings applied to every pooled connection.
+///
+/// The defaults suit a desktop application with a few concurrent writers: WAL journaling,
+/// enforced foreign keys, a 5 second busy timeout, `synchronous=NORMAL`, a 16 MiB page cache,
+/// 256 MiB of memory-mapped I/O and in-memory temporary storage.
+#[derive(Debug, Clone)]
+pub struct PragmaProfile {
+    pub journal_mode: SqliteJournalMode,
+    pub foreign_keys: bool,
+    pub busy_timeout: Duration,
+    pub synchronous: SqliteSynchronous,
+    /// Positive values are pages, negative values are KiB (SQLite's convention).
+    pub cache_size: i64,
+    /// Bytes of the database file to memory-map; 0 disables memory-mapped I/O.
+    pub mmap_size: u64,
+    pub temp_store: TempStore,
+}
+
+impl Default for PragmaProfile {
+    fn default() -> Self {
+        Self {
+            journal_mode: SqliteJournalMode::Wal,
+            foreign_keys: true,
+            busy_timeout: Duration::from_secs(5),
+            synchronous: SqliteSynchronous::Normal,
+            cache_size: -16 * 1024,
+            mmap_size: 256 * 1024 * 1024,
+            temp_store: TempStore::Memory,
+        }
+    }
+}
+
+impl PragmaProfile {
+    pub(crate) fn apply(&self, options: SqliteConnectOptions) -> SqliteConnectOptions {
+        options
+            .journal_mode(self.journal_mode)
+            .foreign_keys(self.foreign_keys)
+            .busy_timeout(self.busy_timeout)
+            .synchronous(self.synchronous)
+            .pragma("cache_size", self.cache_size.to_string())
+            .pragma("mmap_size", self.mmap_size.to_string())
+            .pragma("temp_store", self.temp_store.as_str())
+    }
+}
+
+/// PRAGMA values as reported by a live connection, for diagnostics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectivePragmas {
+    pub journal_mode: String,
+    pub foreign_keys: bool,
+    pub busy_timeout: Duration,
+    pub synchronous: String,
+    pub cache_size: i64,
+    pub mmap_size: i64,
+    pub temp_store: String,
+}
+
+impl EffectivePragmas {
+    pub(crate) async fn read(conn: &mut SqliteConnection) -> Result<Self> {
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&mut *conn).await?;
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(&mut *conn).await?;
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(&mut *conn).await?;
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(&mut *conn).await?;
+        let cache_size: i64 = sqlx::query_scalar("PRAGMA cache_size").fetch_one(&mut *conn).await?;
+        // Databases that cannot be memory-mapped (e.g. in-memory ones) report no row
+        let mmap_size: Option<i64> = sqlx::query_scalar("PRAGMA mmap_size").fetch_optional(&mut *conn).await?;
+        let temp_store: i64 = sqlx::query_scalar("PRAGMA temp_store").fetch_one(&mut *conn).await?;
+
+        Ok(Self {
+            journal_mode: journal_mode.to_uppercase(),
+            foreign_keys: foreign_keys != 0,
+            busy_timeout: Duration::from_millis(busy_timeout.max(0) as u64),
+            synchronous: match synchronous {
+                0 => "OFF",
+                1 => "NORMAL",
+                2 => "FULL",
+                3 => "EXTRA",
+                _ => "UNKNOWN",
+            }
+            .to_string(),
+            cache_size,
+            mmap_size: mmap_size.unwrap_or(0),
+            temp_store: match temp_store {
+                0 => "DEFAULT",
+                1 => "FILE",
+                2 => "MEMORY",
+                _ => "UNKNOWN",
+            }
+            .to_string(),
+        })
+    }
+}
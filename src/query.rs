@@ -0,0 +1,525 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::sqlite::SqliteRow;
+
+use crate::batch::{quote_identifier, OnConflict};
+use crate::database::Database;
+use crate::error::{DatabaseError, Result};
+use crate::pagination::{Page, PageRequest};
+use crate::ser::record_fields;
+use crate::value::Value;
+
+/// Entry point for building SELECT, INSERT, UPDATE and DELETE statements.
+///
+/// Builders compile to SQLite SQL with `?` placeholders plus the values to bind, so
+/// user input never ends up in the SQL text. Table and column names are quoted with
+/// [`quote_identifier`].
+///
+/// ```
+/// use burncloud_database::{col, Query};
+///
+/// let (sql, params) = Query::select("models")
+///     .columns(["id", "name"])
+///     .filter(col("status").eq("ready"))
+///     .filter(col("size").lt(8_000).or(col("provider").is_in(["meta", "mistral"])))
+///     .order_by_desc("size")
+///     .limit(20)
+///     .build();
+/// assert_eq!(
+///     sql,
+///     r#"SELECT "id", "name" FROM "models" WHERE "status" = ? AND ("size" < ? OR "provider" IN (?, ?)) ORDER BY "size" DESC LIMIT 20"#
+/// );
+/// assert_eq!(params.len(), 4);
+/// ```
+pub struct Query;
+
+impl Query {
+    pub fn select(table: &str) -> Select {
+        Select {
+            table: table.to_string(),
+            columns: Vec::new(),
+            filter: Filter::default(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    pub fn insert(table: &str) -> Insert {
+        Insert {
+            table: table.to_string(),
+            columns: Vec::new(),
+            values: Vec::new(),
+            on_conflict: OnConflict::Abort,
+        }
+    }
+
+    pub fn update(table: &str) -> Update {
+        Update {
+            table: table.to_string(),
+            sets: Vec::new(),
+            filter: Filter::default(),
+        }
+    }
+
+    pub fn delete(table: &str) -> Delete {
+        Delete {
+            table: table.to_string(),
+            filter: Filter::default(),
+        }
+    }
+}
+
+/// A column to build conditions on.
+///
+/// The name is quoted as one identifier, so `col("models.id")` means a column literally
+/// named `models.id`. Use [`Condition::raw`] for table-qualified columns.
+pub fn col(name: &str) -> Column {
+    Column(quote_identifier(name))
+}
+
+#[derive(Debug, Clone)]
+pub struct Column(String);
+
+impl Column {
+    pub fn eq(self, value: impl Into<Value>) -> Condition {
+        self.compare("=", value)
+    }
+
+    pub fn ne(self, value: impl Into<Value>) -> Condition {
+        self.compare("<>", value)
+    }
+
+    pub fn lt(self, value: impl Into<Value>) -> Condition {
+        self.compare("<", value)
+    }
+
+    pub fn le(self, value: impl Into<Value>) -> Condition {
+        self.compare("<=", value)
+    }
+
+    pub fn gt(self, value: impl Into<Value>) -> Condition {
+        self.compare(">", value)
+    }
+
+    pub fn ge(self, value: impl Into<Value>) -> Condition {
+        self.compare(">=", value)
+    }
+
+    /// `LIKE` with a caller-supplied pattern, so `%` and `_` are wildcards.
+    pub fn like(self, pattern: impl Into<String>) -> Condition {
+        self.compare("LIKE", pattern.into())
+    }
+
+    /// Substring match (case-insensitive for ASCII) with `%` and `_` matched literally.
+    pub fn contains(self, text: &str) -> Condition {
+        let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        Condition::sql(format!("{} LIKE ? ESCAPE '\\'", self.0), [format!("%{}%", escaped)])
+    }
+
+    pub fn is_null(self) -> Condition {
+        Condition::sql(format!("{} IS NULL", self.0), Vec::<Value>::new())
+    }
+
+    pub fn is_not_null(self) -> Condition {
+        Condition::sql(format!("{} IS NOT NULL", self.0), Vec::<Value>::new())
+    }
+
+    pub fn is_in<I>(self, values: I) -> Condition
+    where
+        I: IntoIterator,
+        I::Item: Into<Value>,
+    {
+        self.list("IN", values)
+    }
+
+    pub fn not_in<I>(self, values: I) -> Condition
+    where
+        I: IntoIterator,
+        I::Item: Into<Value>,
+    {
+        self.list("NOT IN", values)
+    }
+
+    pub fn between(self, low: impl Into<Value>, high: impl Into<Value>) -> Condition {
+        Condition::sql(format!("{} BETWEEN ? AND ?", self.0), [low.into(), high.into()])
+    }
+
+    fn compare(self, op: &str, value: impl Into<Value>) -> Condition {
+        Condition::sql(format!("{} {} ?", self.0, op), [value.into()])
+    }
+
+    fn list<I>(self, op: &str, values: I) -> Condition
+    where
+        I: IntoIterator,
+        I::Item: Into<Value>,
+    {
+        let values: Vec<Value> = values.into_iter().map(Into::into).collect();
+        let placeholders = vec!["?"; values.len()].join(", ");
+        Condition::sql(format!("{} {} ({})", self.0, op, placeholders), values)
+    }
+}
+
+/// A WHERE condition, combined with [`Condition::and`], [`Condition::or`] and `!`.
+#[derive(Debug, Clone)]
+pub struct Condition(Node);
+
+#[derive(Debug, Clone)]
+enum Node {
+    Sql { sql: String, values: Vec<Value> },
+    And(Vec<Node>),
+    Or(Vec<Node>),
+    Not(Box<Node>),
+}
+
+impl Condition {
+    /// A condition written in SQL with `?` placeholders, for anything the builder lacks.
+    pub fn raw<I>(sql: &str, values: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Value>,
+    {
+        // Parenthesized so an OR inside cannot leak into the surrounding condition
+        Self::sql(format!("({})", sql), values)
+    }
+
+    fn sql<I>(sql: String, values: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Value>,
+    {
+        Condition(Node::Sql {
+            sql,
+            values: values.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    pub fn and(self, other: Condition) -> Self {
+        match self.0 {
+            Node::And(mut nodes) => {
+                nodes.push(other.0);
+                Condition(Node::And(nodes))
+            }
+            node => Condition(Node::And(vec![node, other.0])),
+        }
+    }
+
+    pub fn or(self, other: Condition) -> Self {
+        match self.0 {
+            Node::Or(mut nodes) => {
+                nodes.push(other.0);
+                Condition(Node::Or(nodes))
+            }
+            node => Condition(Node::Or(vec![node, other.0])),
+        }
+    }
+}
+
+impl std::ops::Not for Condition {
+    type Output = Condition;
+
+    fn not(self) -> Condition {
+        Condition(Node::Not(Box::new(self.0)))
+    }
+}
+
+impl Node {
+    fn write(&self, sql: &mut String, values: &mut Vec<Value>) {
+        match self {
+            Node::Sql { sql: text, values: bound } => {
+                sql.push_str(text);
+                values.extend(bound.iter().cloned());
+            }
+            Node::And(nodes) => write_group(nodes, " AND ", "1", sql, values),
+            Node::Or(nodes) => write_group(nodes, " OR ", "0", sql, values),
+            Node::Not(node) => {
+                sql.push_str("NOT ");
+                node.write_nested(sql, values);
+            }
+        }
+    }
+
+    // Groups are parenthesized when they appear inside another condition
+    fn write_nested(&self, sql: &mut String, values: &mut Vec<Value>) {
+        match self {
+            Node::And(nodes) | Node::Or(nodes) if nodes.len() > 1 => {
+                sql.push('(');
+                self.write(sql, values);
+                sql.push(')');
+            }
+            _ => self.write(sql, values),
+        }
+    }
+}
+
+// An empty AND is true and an empty OR is false
+fn write_group(nodes: &[Node], separator: &str, empty: &str, sql: &mut String, values: &mut Vec<Value>) {
+    if nodes.is_empty() {
+        sql.push_str(empty);
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            sql.push_str(separator);
+        }
+        node.write_nested(sql, values);
+    }
+}
+
+// Conditions added with repeated filter() calls, joined with AND
+#[derive(Debug, Clone, Default)]
+struct Filter(Vec<Node>);
+
+impl Filter {
+    fn push(&mut self, condition: Condition) {
+        self.0.push(condition.0);
+    }
+
+    fn write(&self, sql: &mut String, values: &mut Vec<Value>) {
+        if !self.0.is_empty() {
+            sql.push_str(" WHERE ");
+            write_group(&self.0, " AND ", "1", sql, values);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Select {
+    table: String,
+    columns: Vec<String>,
+    filter: Filter,
+    order_by: Vec<String>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl Select {
+    /// The columns to return; all columns (`*`) if never called.
+    pub fn columns<I>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.columns.extend(columns.into_iter().map(|c| quote_identifier(c.as_ref())));
+        self
+    }
+
+    /// Adds a condition; conditions from repeated calls must all hold.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filter.push(condition);
+        self
+    }
+
+    pub fn order_by(mut self, column: &str) -> Self {
+        self.order_by.push(quote_identifier(column));
+        self
+    }
+
+    pub fn order_by_desc(mut self, column: &str) -> Self {
+        self.order_by.push(format!("{} DESC", quote_identifier(column)));
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn build(&self) -> (String, Vec<Value>) {
+        let columns = if self.columns.is_empty() { "*".to_string() } else { self.columns.join(", ") };
+        let mut sql = format!("SELECT {} FROM {}", columns, quote_identifier(&self.table));
+        let mut values = Vec::new();
+        self.filter.write(&mut sql, &mut values);
+        if !self.order_by.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&self.order_by.join(", "));
+        }
+        // SQLite only accepts OFFSET after a LIMIT; -1 means no limit
+        match (self.limit, self.offset) {
+            (Some(limit), Some(offset)) => sql.push_str(&format!(" LIMIT {} OFFSET {}", limit, offset)),
+            (Some(limit), None) => sql.push_str(&format!(" LIMIT {}", limit)),
+            (None, Some(offset)) => sql.push_str(&format!(" LIMIT -1 OFFSET {}", offset)),
+            (None, None) => {}
+        }
+        (sql, values)
+    }
+
+    /// `SELECT COUNT(*)` over the filtered rows, ignoring ordering, limit and offset.
+    pub fn build_count(&self) -> (String, Vec<Value>) {
+        let mut sql = format!("SELECT COUNT(*) FROM {}", quote_identifier(&self.table));
+        let mut values = Vec::new();
+        self.filter.write(&mut sql, &mut values);
+        (sql, values)
+    }
+
+    pub async fn fetch_all<T>(&self, db: &Database) -> Result<Vec<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        let (sql, values) = self.build();
+        db.fetch_all_with_params(&sql, values).await
+    }
+
+    pub async fn fetch_one<T>(&self, db: &Database) -> Result<T>
+    where
+        T: for<'r> sqlx::FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        let (sql, values) = self.build();
+        db.fetch_one_with_params(&sql, values).await
+    }
+
+    pub async fn fetch_optional<T>(&self, db: &Database) -> Result<Option<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        let (sql, values) = self.build();
+        db.fetch_optional_with_params(&sql, values).await
+    }
+
+    pub async fn fetch_all_as<T: DeserializeOwned>(&self, db: &Database) -> Result<Vec<T>> {
+        let (sql, values) = self.build();
+        db.fetch_all_as(&sql, values).await
+    }
+
+    pub async fn count(&self, db: &Database) -> Result<i64> {
+        let (sql, values) = self.build_count();
+        db.fetch_scalar_with_params(&sql, values).await
+    }
+
+    /// One page of the selected rows, replacing this builder's limit and offset. Keyset
+    /// requests also replace its ordering.
+    pub async fn paginate<T>(&self, db: &Database, request: &PageRequest) -> Result<Page<T>>
+    where
+        T: for<'r> sqlx::FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        let select = Select {
+            limit: None,
+            offset: None,
+            ..self.clone()
+        };
+        let (sql, values) = select.build();
+        db.paginate(&sql, values, request).await
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Insert {
+    table: String,
+    columns: Vec<String>,
+    values: Vec<Value>,
+    on_conflict: OnConflict,
+}
+
+impl Insert {
+    pub fn value(mut self, column: &str, value: impl Into<Value>) -> Self {
+        self.columns.push(quote_identifier(column));
+        self.values.push(value.into());
+        self
+    }
+
+    /// Adds a value for every field of `record`, which must serialize to an object.
+    pub fn record<T: Serialize + ?Sized>(mut self, record: &T) -> Result<Self> {
+        for (column, value) in record_fields(record, 0)? {
+            self = self.value(&column, value);
+        }
+        Ok(self)
+    }
+
+    pub fn on_conflict(mut self, on_conflict: OnConflict) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    pub fn build(&self) -> (String, Vec<Value>) {
+        let verb = self.on_conflict.insert_sql();
+        let sql = if self.columns.is_empty() {
+            format!("{} {} DEFAULT VALUES", verb, quote_identifier(&self.table))
+        } else {
+            format!(
+                "{} {} ({}) VALUES ({})",
+                verb,
+                quote_identifier(&self.table),
+                self.columns.join(", "),
+                vec!["?"; self.values.len()].join(", ")
+            )
+        };
+        (sql, self.values.clone())
+    }
+
+    /// Runs the INSERT and returns the number of rows inserted.
+    pub async fn execute(&self, db: &Database) -> Result<u64> {
+        let (sql, values) = self.build();
+        Ok(db.execute_query_with_params(&sql, values).await?.rows_affected())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Update {
+    table: String,
+    sets: Vec<(String, Value)>,
+    filter: Filter,
+}
+
+impl Update {
+    pub fn set(mut self, column: &str, value: impl Into<Value>) -> Self {
+        self.sets.push((quote_identifier(column), value.into()));
+        self
+    }
+
+    /// Restricts the rows updated; without a filter every row is updated.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filter.push(condition);
+        self
+    }
+
+    /// Fails if no column was `set`, since SQLite has no empty UPDATE.
+    pub fn build(&self) -> Result<(String, Vec<Value>)> {
+        if self.sets.is_empty() {
+            return Err(DatabaseError::InvalidData {
+                message: format!("update of {} sets no columns", self.table),
+            });
+        }
+        let assignments: Vec<String> = self.sets.iter().map(|(column, _)| format!("{} = ?", column)).collect();
+        let mut sql = format!("UPDATE {} SET {}", quote_identifier(&self.table), assignments.join(", "));
+        let mut values: Vec<Value> = self.sets.iter().map(|(_, value)| value.clone()).collect();
+        self.filter.write(&mut sql, &mut values);
+        Ok((sql, values))
+    }
+
+    /// Runs the UPDATE and returns the number of rows changed.
+    pub async fn execute(&self, db: &Database) -> Result<u64> {
+        let (sql, values) = self.build()?;
+        Ok(db.execute_query_with_params(&sql, values).await?.rows_affected())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Delete {
+    table: String,
+    filter: Filter,
+}
+
+impl Delete {
+    /// Restricts the rows deleted; without a filter every row is deleted.
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.filter.push(condition);
+        self
+    }
+
+    pub fn build(&self) -> (String, Vec<Value>) {
+        let mut sql = format!("DELETE FROM {}", quote_identifier(&self.table));
+        let mut values = Vec::new();
+        self.filter.write(&mut sql, &mut values);
+        (sql, values)
+    }
+
+    /// Runs the DELETE and returns the number of rows deleted.
+    pub async fn execute(&self, db: &Database) -> Result<u64> {
+        let (sql, values) = self.build();
+        Ok(db.execute_query_with_params(&sql, values).await?.rows_affected())
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::sqlite::SqliteRow;
+
+use crate::batch::quote_identifier;
+use crate::database::Database;
+use crate::error::{DatabaseError, Result};
+use crate::ser::record_fields;
+use crate::value::Value;
+
+/// A type stored as one row per value in a table.
+///
+/// `column_values` and `from_sql_row` map the type to and from the columns in `COLUMNS`.
+/// With the `derive` feature, `#[derive(Entity)]` generates the whole implementation. By
+/// hand, [`serialize_columns`] and [`from_row`](crate::from_row) map through serde. A primary
+/// key that is NULL is left out of inserts so SQLite can assign it.
+///
+/// ```
+/// use burncloud_database::repository::serialize_columns;
+/// use burncloud_database::sqlx::sqlite::SqliteRow;
+/// use burncloud_database::{from_row, Entity, Result, Value};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Provider {
+///     id: Option<i64>,
+///     name: String,
+///     base_url: String,
+/// }
+///
+/// impl Entity for Provider {
+///     type Id = i64;
+///     const TABLE: &'static str = "providers";
+///     const COLUMNS: &'static [&'static str] = &["id", "name", "base_url"];
+///
+///     fn column_values(&self) -> Result<Vec<Value>> {
+///         serialize_columns(self, Self::COLUMNS)
+///     }
+///
+///     fn from_sql_row(row: &SqliteRow) -> Result<Self> {
+///         from_row(row)
+///     }
+/// }
+/// ```
+pub trait Entity: Sized + Send + Sync {
+    type Id: Into<Value> + Send;
+
+    const TABLE: &'static str;
+    const PRIMARY_KEY: &'static str = "id";
+    const COLUMNS: &'static [&'static str];
+    /// Set to the current time on insert.
+    const CREATED_AT: Option<&'static str> = None;
+    /// Set to the current time on insert and update.
+    const UPDATED_AT: Option<&'static str> = None;
+
+    /// One value per column in `COLUMNS`, in the same order.
+    fn column_values(&self) -> Result<Vec<Value>>;
+
+    fn from_sql_row(row: &SqliteRow) -> Result<Self>;
+}
+
+/// The serde fields of `value` named by `columns`, in order, for hand-written
+/// [`Entity::column_values`] implementations.
+pub fn serialize_columns<T: Serialize + ?Sized>(value: &T, columns: &[&str]) -> Result<Vec<Value>> {
+    let mut fields: HashMap<String, Value> = record_fields(value, 0)?.into_iter().collect();
+    columns
+        .iter()
+        .map(|&column| {
+            fields.remove(column).ok_or_else(|| DatabaseError::InvalidData {
+                message: format!("no serialized field for column {}", column),
+            })
+        })
+        .collect()
+}
+
+/// Generic CRUD for an [`Entity`], created with [`Database::repository`].
+pub struct Repository<'a, E> {
+    db: &'a Database,
+    entity: PhantomData<fn() -> E>,
+}
+
+impl<'a, E: Entity> Repository<'a, E> {
+    pub fn new(db: &'a Database) -> Self {
+        Self {
+            db,
+            entity: PhantomData,
+        }
+    }
+
+    pub fn database(&self) -> &'a Database {
+        self.db
+    }
+
+    pub async fn get(&self, id: E::Id) -> Result<Option<E>> {
+        let sql = format!("SELECT {} FROM {} WHERE {} = ?", select_list::<E>(), table::<E>(), primary_key::<E>());
+        let rows = self.db.query_with_params(&sql, [id.into()]).await?;
+        rows.first().map(E::from_sql_row).transpose()
+    }
+
+    /// Inserts `entity` and returns the stored row, including its assigned primary key and
+    /// any column defaults.
+    pub async fn insert(&self, entity: &E) -> Result<E> {
+        let now = Value::from(Utc::now());
+        let mut columns = Vec::with_capacity(E::COLUMNS.len());
+        let mut values = Vec::with_capacity(E::COLUMNS.len());
+        for (column, value) in entity_values(entity)? {
+            if column == E::PRIMARY_KEY && value.is_null() {
+                continue;
+            }
+            let is_timestamp = Some(column) == E::CREATED_AT || Some(column) == E::UPDATED_AT;
+            columns.push(quote_identifier(column));
+            values.push(if is_timestamp { now.clone() } else { value });
+        }
+
+        let values_sql = if columns.is_empty() {
+            "DEFAULT VALUES".to_string()
+        } else {
+            format!("({}) VALUES ({})", columns.join(", "), vec!["?"; values.len()].join(", "))
+        };
+        let sql = format!("INSERT INTO {} {} RETURNING {}", table::<E>(), values_sql, select_list::<E>());
+        let rows = self.db.query_with_params(&sql, values).await?;
+        let row = rows.first().ok_or(DatabaseError::Connection(sqlx::Error::RowNotFound))?;
+        E::from_sql_row(row)
+    }
+
+    /// Writes every column of `entity` to the row with its primary key. Returns `false` if
+    /// no such row exists.
+    pub async fn update(&self, entity: &E) -> Result<bool> {
+        let mut assignments = Vec::with_capacity(E::COLUMNS.len());
+        let mut values = Vec::with_capacity(E::COLUMNS.len());
+        let mut id = None;
+        for (column, value) in entity_values(entity)? {
+            if column == E::PRIMARY_KEY {
+                id = Some(value);
+            } else if Some(column) == E::UPDATED_AT {
+                assignments.push(format!("{} = ?", quote_identifier(column)));
+                values.push(Value::from(Utc::now()));
+            } else if Some(column) != E::CREATED_AT {
+                assignments.push(format!("{} = ?", quote_identifier(column)));
+                values.push(value);
+            }
+        }
+        let id = id.ok_or_else(|| missing_column::<E>(E::PRIMARY_KEY))?;
+        if assignments.is_empty() {
+            return self.exists_value(id).await;
+        }
+        values.push(id);
+
+        let sql = format!("UPDATE {} SET {} WHERE {} = ?", table::<E>(), assignments.join(", "), primary_key::<E>());
+        Ok(self.db.execute_query_with_params(&sql, values).await?.rows_affected() > 0)
+    }
+
+    /// Deletes the row with primary key `id`. Returns `false` if no such row exists.
+    pub async fn delete(&self, id: E::Id) -> Result<bool> {
+        let sql = format!("DELETE FROM {} WHERE {} = ?", table::<E>(), primary_key::<E>());
+        Ok(self.db.execute_query_with_params(&sql, [id.into()]).await?.rows_affected() > 0)
+    }
+
+    /// All rows, ordered by primary key.
+    pub async fn list(&self) -> Result<Vec<E>> {
+        let sql = format!("SELECT {} FROM {} ORDER BY {}", select_list::<E>(), table::<E>(), primary_key::<E>());
+        self.db.query(&sql).await?.iter().map(E::from_sql_row).collect()
+    }
+
+    pub async fn count(&self) -> Result<i64> {
+        self.db.fetch_scalar(&format!("SELECT COUNT(*) FROM {}", table::<E>())).await
+    }
+
+    pub async fn exists(&self, id: E::Id) -> Result<bool> {
+        self.exists_value(id.into()).await
+    }
+
+    async fn exists_value(&self, id: Value) -> Result<bool> {
+        let sql = format!("SELECT EXISTS (SELECT 1 FROM {} WHERE {} = ?)", table::<E>(), primary_key::<E>());
+        self.db.fetch_scalar_with_params(&sql, [id]).await
+    }
+}
+
+fn table<E: Entity>() -> String {
+    quote_identifier(E::TABLE)
+}
+
+fn primary_key<E: Entity>() -> String {
+    quote_identifier(E::PRIMARY_KEY)
+}
+
+fn select_list<E: Entity>() -> String {
+    E::COLUMNS.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", ")
+}
+
+// The entity's value for each of its columns, in COLUMNS order
+fn entity_values<E: Entity>(entity: &E) -> Result<Vec<(&'static str, Value)>> {
+    let values = entity.column_values()?;
+    if values.len() != E::COLUMNS.len() {
+        return Err(DatabaseError::InvalidData {
+            message: format!("{} has {} columns but produced {} values", E::TABLE, E::COLUMNS.len(), values.len()),
+        });
+    }
+    Ok(E::COLUMNS.iter().copied().zip(values).collect())
+}
+
+fn missing_column<E: Entity>(column: &str) -> DatabaseError {
+    DatabaseError::InvalidData {
+        message: format!("{} has no column for its primary key {}", E::TABLE, column),
+    }
+}
@@ -0,0 +1,99 @@
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::Result;
+
+/// Retries operations that fail because the database is busy or locked.
+///
+/// Attempt `n` (counting from 1) that fails with `SQLITE_BUSY` or `SQLITE_LOCKED` is followed
+/// by a sleep of `initial_backoff * multiplier^(n-1)`, capped at `max_backoff`. With `jitter`
+/// the sleep is drawn uniformly from the upper half of that delay so that competing writers
+/// spread out. Every other error is returned immediately.
+///
+/// Busy errors normally surface only after the connection's `busy_timeout` has elapsed, so
+/// keep `max_attempts` small; the exception is a deferred transaction upgrading to a write
+/// lock in WAL mode, which fails at once and can only succeed by restarting the transaction
+/// (see `Database::retry_transaction`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that runs every operation exactly once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// The sleep after failed attempt `attempt` (1-based), before jitter is applied.
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        // Huge caps such as Duration::MAX do not survive the round trip through f64
+        Duration::try_from_secs_f64(delay).map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+    }
+
+    /// Runs `operation` until it succeeds, fails with a non-retryable error or runs out of
+    /// attempts. Use it to wrap raw sqlx calls made through `DatabaseConnection::pool()`.
+    pub async fn run<F, Fut, T>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(e) if attempt < self.max_attempts && e.is_retryable() => {
+                    tokio::time::sleep(self.sleep_for(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn sleep_for(&self, attempt: u32) -> Duration {
+        let delay = self.delay_for(attempt);
+        if self.jitter {
+            let jittered = delay.as_secs_f64() * (0.5 + fastrand::f64() / 2.0);
+            Duration::try_from_secs_f64(jittered).unwrap_or(delay)
+        } else {
+            delay
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::error::{DatabaseError, Result};
+use crate::executor;
+use crate::transaction::Transaction;
+
+/// Splits a SQL script into its statements, without the trailing semicolons.
+///
+/// Semicolons inside string literals, quoted identifiers, comments and the `BEGIN ... END`
+/// body of a `CREATE TRIGGER` do not end a statement. Empty and comment-only statements are
+/// dropped.
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    statements(sql).into_iter().map(|(_, statement)| statement).collect()
+}
+
+// Runs each statement of `sql` on the transaction, stopping at the first failure
+pub(crate) async fn run(tx: &mut Transaction, sql: &str) -> Result<usize> {
+    let statements = unwrap_transaction(statements(sql).into_iter().enumerate().collect())?;
+    for (index, (offset, statement)) in &statements {
+        if let Err(e) = executor::execute(tx.connection()?, statement, None).await {
+            return Err(DatabaseError::Script {
+                statement: index + 1,
+                line: sql[..*offset].matches('\n').count() + 1,
+                source: Box::new(e),
+            });
+        }
+    }
+    Ok(statements.len())
+}
+
+// Drops a BEGIN ... COMMIT/END that wraps the whole script, as in `sqlite3 .dump` output
+// (where only PRAGMAs precede the BEGIN), since the script already runs in a transaction.
+// Any other transaction control would end that transaction halfway through the script, so
+// it is rejected before anything runs.
+fn unwrap_transaction(mut statements: Vec<(usize, (usize, &str))>) -> Result<Vec<(usize, (usize, &str))>> {
+    let keyword = |statement: &(usize, (usize, &str))| first_keyword(statement.1 .1);
+    let begin = statements.iter().position(|statement| keyword(statement) != "PRAGMA");
+    if let Some(begin) = begin.filter(|&begin| keyword(&statements[begin]) == "BEGIN") {
+        let wrapped = statements.len() > begin + 1
+            && statements.last().is_some_and(|last| matches!(keyword(last).as_str(), "COMMIT" | "END"));
+        if wrapped {
+            statements.pop();
+            statements.remove(begin);
+        }
+    }
+
+    if let Some((_, (_, statement))) = statements.iter().find(|(_, (_, statement))| is_transaction_control(statement)) {
+        return Err(DatabaseError::InvalidData {
+            message: format!(
+                "scripts run in a transaction of their own and can only be wrapped in BEGIN ... COMMIT as a whole, found '{}'",
+                statement
+            ),
+        });
+    }
+    Ok(statements)
+}
+
+fn first_keyword(statement: &str) -> String {
+    words(statement).next().unwrap_or_default()
+}
+
+// ROLLBACK TO a savepoint is fine inside the script's transaction; ROLLBACK alone is not
+fn is_transaction_control(statement: &str) -> bool {
+    match first_keyword(statement).as_str() {
+        "BEGIN" | "COMMIT" | "END" => true,
+        "ROLLBACK" => !words(statement).any(|word| word == "TO"),
+        _ => false,
+    }
+}
+
+fn words(statement: &str) -> impl Iterator<Item = String> + '_ {
+    statement
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_uppercase)
+}
+
+// Each statement with its byte offset in `sql`
+fn statements(sql: &str) -> Vec<(usize, &str)> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut splitter = Splitter::default();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+                continue;
+            }
+            quote @ (b'\'' | b'"' | b'`' | b'[') => {
+                let close = if quote == b'[' { b']' } else { quote };
+                let start = i;
+                i += 1;
+                // Doubled quotes are escapes and simply reopen the literal
+                while i < bytes.len() && bytes[i] != close {
+                    i += 1;
+                }
+                i = (i + 1).min(bytes.len());
+                splitter.token(start, i, None);
+                continue;
+            }
+            b';' if splitter.block_depth == 0 => {
+                if let Some(statement) = splitter.finish(sql) {
+                    statements.push(statement);
+                }
+            }
+            b if b.is_ascii_alphanumeric() || b == b'_' => {
+                let start = i;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                splitter.token(start, i, Some(&sql[start..i]));
+                continue;
+            }
+            b if b.is_ascii_whitespace() => {}
+            _ => {
+                let len = sql[i..].chars().next().map_or(1, char::len_utf8);
+                splitter.token(i, i + len, None);
+                i += len;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    if let Some(statement) = splitter.finish(sql) {
+        statements.push(statement);
+    }
+    statements
+}
+
+#[derive(Default)]
+struct Splitter {
+    // Byte range of the current statement, from its first to its last token
+    start: Option<usize>,
+    end: usize,
+    tokens: usize,
+    // Leading keywords, enough to recognize CREATE [TEMP] TRIGGER
+    keywords: Vec<String>,
+    in_trigger: bool,
+    // Open BEGIN/CASE blocks inside a trigger body
+    block_depth: usize,
+}
+
+impl Splitter {
+    fn token(&mut self, start: usize, end: usize, word: Option<&str>) {
+        // Keywords are only collected while every token so far has been one
+        let leading = self.tokens == self.keywords.len();
+        self.tokens += 1;
+        self.start.get_or_insert(start);
+        self.end = end;
+
+        let Some(word) = word.map(str::to_ascii_uppercase) else {
+            return;
+        };
+        if self.in_trigger {
+            match word.as_str() {
+                "BEGIN" | "CASE" => self.block_depth += 1,
+                "END" => self.block_depth = self.block_depth.saturating_sub(1),
+                _ => {}
+            }
+        } else if leading && self.keywords.len() < 3 {
+            self.keywords.push(word);
+            self.in_trigger = matches!(
+                self.keywords.iter().map(String::as_str).collect::<Vec<_>>().as_slice(),
+                ["CREATE", "TRIGGER"] | ["CREATE", "TEMP" | "TEMPORARY", "TRIGGER"]
+            );
+        }
+    }
+
+    fn finish<'a>(&mut self, sql: &'a str) -> Option<(usize, &'a str)> {
+        let start = self.start?;
+        let end = self.end;
+        *self = Splitter::default();
+        Some((start, &sql[start..end]))
+    }
+}
@@ -0,0 +1,666 @@
+use chrono::{DateTime, Utc};
+use serde::ser::{self, Error as _, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple};
+use serde_json::value::Serializer as JsonSerializer;
+use serde_json::Value as JsonValue;
+
+use crate::error::{DatabaseError, Result};
+use crate::value::Value;
+
+type Error = serde_json::Error;
+
+const NOT_AN_OBJECT: &str = "must serialize to an object";
+
+// A record's fields in declaration order, each bound the way `params!` binds the field's
+// type: bytes (`Vec<u8>`, `[u8; N]`, serde_bytes) become BLOBs, `DateTime<Utc>` becomes
+// `Value::DateTime`, scalars their natural variant and anything nested JSON text. An empty
+// sequence is JSON `[]`, since it cannot be told apart from an empty `Vec<u8>`
+pub(crate) fn record_fields<T: Serialize + ?Sized>(record: &T, index: usize) -> Result<Vec<(String, Value)>> {
+    record.serialize(RecordSerializer).map_err(|e| {
+        if e.to_string() == NOT_AN_OBJECT {
+            DatabaseError::InvalidData {
+                message: format!("record {} {}", index, NOT_AN_OBJECT),
+            }
+        } else {
+            e.into()
+        }
+    })
+}
+
+fn not_an_object<T>() -> std::result::Result<T, Error> {
+    Err(Error::custom(NOT_AN_OBJECT))
+}
+
+struct RecordSerializer;
+
+impl ser::Serializer for RecordSerializer {
+    type Ok = Vec<(String, Value)>;
+    type Error = Error;
+    type SerializeSeq = Impossible<Self::Ok, Error>;
+    type SerializeTuple = Impossible<Self::Ok, Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Error>;
+    type SerializeMap = Fields;
+    type SerializeStruct = Fields;
+    type SerializeStructVariant = Impossible<Self::Ok, Error>;
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> std::result::Result<Fields, Error> {
+        Ok(Fields::with_capacity(len))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> std::result::Result<Fields, Error> {
+        Ok(Fields::with_capacity(len.unwrap_or(0)))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> std::result::Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> std::result::Result<Self::Ok, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_bool(self, _v: bool) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_i8(self, _v: i8) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_i16(self, _v: i16) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_i32(self, _v: i32) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_i64(self, _v: i64) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_u8(self, _v: u8) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_u16(self, _v: u16) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_u32(self, _v: u32) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_u64(self, _v: u64) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_f32(self, _v: f32) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_f64(self, _v: f64) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_char(self, _v: char) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_str(self, _v: &str) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_none(self) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_unit(self) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, _variant: &'static str) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> std::result::Result<Self::Ok, Error> {
+        not_an_object()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> std::result::Result<Self::SerializeSeq, Error> {
+        not_an_object()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> std::result::Result<Self::SerializeTuple, Error> {
+        not_an_object()
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> std::result::Result<Self::SerializeTupleStruct, Error> {
+        not_an_object()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeTupleVariant, Error> {
+        not_an_object()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeStructVariant, Error> {
+        not_an_object()
+    }
+}
+
+struct Fields {
+    fields: Vec<(String, Value)>,
+    // Set between SerializeMap::serialize_key and serialize_value
+    key: Option<String>,
+}
+
+impl Fields {
+    fn with_capacity(len: usize) -> Self {
+        Self {
+            fields: Vec::with_capacity(len),
+            key: None,
+        }
+    }
+}
+
+impl SerializeStruct for Fields {
+    type Ok = Vec<(String, Value)>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> std::result::Result<(), Error> {
+        self.fields.push((key.to_string(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<Self::Ok, Error> {
+        Ok(self.fields)
+    }
+}
+
+impl SerializeMap for Fields {
+    type Ok = Vec<(String, Value)>;
+    type Error = Error;
+
+    // Keys are named like JSON object keys, so numbers become their text
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> std::result::Result<(), Error> {
+        self.key = Some(match key.serialize(JsonSerializer)? {
+            JsonValue::String(key) => key,
+            JsonValue::Number(key) => key.to_string(),
+            JsonValue::Bool(key) => key.to_string(),
+            _ => return Err(Error::custom("record keys must be strings")),
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        let key = self.key.take().ok_or_else(|| Error::custom("record value without a key"))?;
+        self.fields.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> std::result::Result<Self::Ok, Error> {
+        Ok(self.fields)
+    }
+}
+
+// One field of a record
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = Sequence;
+    type SerializeTuple = Sequence;
+    type SerializeTupleStruct = Json<<JsonSerializer as ser::Serializer>::SerializeTupleStruct>;
+    type SerializeTupleVariant = Json<<JsonSerializer as ser::Serializer>::SerializeTupleVariant>;
+    type SerializeMap = Json<<JsonSerializer as ser::Serializer>::SerializeMap>;
+    type SerializeStruct = Json<<JsonSerializer as ser::Serializer>::SerializeStruct>;
+    type SerializeStructVariant = Json<<JsonSerializer as ser::Serializer>::SerializeStructVariant>;
+
+    fn serialize_bool(self, v: bool) -> std::result::Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> std::result::Result<Value, Error> {
+        Ok(i64::try_from(v).map_or(Value::Real(v as f64), Value::Integer))
+    }
+
+    fn serialize_u8(self, v: u8) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    // Beyond i64::MAX SQLite can only store the value as REAL
+    fn serialize_u64(self, v: u64) -> std::result::Result<Value, Error> {
+        Ok(i64::try_from(v).map_or(Value::Real(v as f64), Value::Integer))
+    }
+
+    fn serialize_u128(self, v: u128) -> std::result::Result<Value, Error> {
+        Ok(i64::try_from(v).map_or(Value::Real(v as f64), Value::Integer))
+    }
+
+    fn serialize_f32(self, v: f32) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> std::result::Result<Value, Error> {
+        Ok(Value::Text(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    // chrono serializes DateTime<Utc> through collect_str, as RFC 3339 ending in `Z`
+    fn collect_str<T: std::fmt::Display + ?Sized>(self, value: &T) -> std::result::Result<Value, Error> {
+        let text = value.to_string();
+        match DateTime::parse_from_rfc3339(&text) {
+            Ok(at) if text.ends_with('Z') => Ok(Value::DateTime(at.with_timezone(&Utc))),
+            _ => Ok(Value::Text(text)),
+        }
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> std::result::Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_none(self) -> std::result::Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> std::result::Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> std::result::Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> std::result::Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> std::result::Result<Value, Error> {
+        Ok(Value::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> std::result::Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> std::result::Result<Value, Error> {
+        JsonSerializer.serialize_newtype_variant(name, index, variant, value).map(Value::Json)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> std::result::Result<Sequence, Error> {
+        Ok(Sequence {
+            bytes: Some(Vec::with_capacity(len.unwrap_or(0))),
+            json: JsonSerializer.serialize_seq(len)?,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> std::result::Result<Sequence, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, name: &'static str, len: usize) -> std::result::Result<Self::SerializeTupleStruct, Error> {
+        JsonSerializer.serialize_tuple_struct(name, len).map(Json)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self::SerializeTupleVariant, Error> {
+        JsonSerializer.serialize_tuple_variant(name, index, variant, len).map(Json)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> std::result::Result<Self::SerializeMap, Error> {
+        JsonSerializer.serialize_map(len).map(Json)
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> std::result::Result<Self::SerializeStruct, Error> {
+        JsonSerializer.serialize_struct(name, len).map(Json)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> std::result::Result<Self::SerializeStructVariant, Error> {
+        JsonSerializer.serialize_struct_variant(name, index, variant, len).map(Json)
+    }
+}
+
+// Builds the JSON array and, while every element is a u8, the bytes
+struct Sequence {
+    bytes: Option<Vec<u8>>,
+    json: <JsonSerializer as ser::Serializer>::SerializeSeq,
+}
+
+impl SerializeSeq for Sequence {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        if let Some(bytes) = &mut self.bytes {
+            match value.serialize(ByteSerializer) {
+                Ok(byte) => bytes.push(byte),
+                Err(_) => self.bytes = None,
+            }
+        }
+        SerializeSeq::serialize_element(&mut self.json, value)
+    }
+
+    fn end(self) -> std::result::Result<Value, Error> {
+        match self.bytes {
+            Some(bytes) if !bytes.is_empty() => Ok(Value::Blob(bytes)),
+            _ => SerializeSeq::end(self.json).map(Value::Json),
+        }
+    }
+}
+
+impl SerializeTuple for Sequence {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> std::result::Result<Value, Error> {
+        SerializeSeq::end(self)
+    }
+}
+
+// Nested values other than sequences are stored as JSON text
+struct Json<S>(S);
+
+impl<S: ser::SerializeTupleStruct<Ok = JsonValue, Error = Error>> ser::SerializeTupleStruct for Json<S> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.0.serialize_field(value)
+    }
+
+    fn end(self) -> std::result::Result<Value, Error> {
+        self.0.end().map(Value::Json)
+    }
+}
+
+impl<S: ser::SerializeTupleVariant<Ok = JsonValue, Error = Error>> ser::SerializeTupleVariant for Json<S> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.0.serialize_field(value)
+    }
+
+    fn end(self) -> std::result::Result<Value, Error> {
+        self.0.end().map(Value::Json)
+    }
+}
+
+impl<S: SerializeMap<Ok = JsonValue, Error = Error>> SerializeMap for Json<S> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> std::result::Result<(), Error> {
+        self.0.serialize_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> std::result::Result<(), Error> {
+        self.0.serialize_value(value)
+    }
+
+    fn end(self) -> std::result::Result<Value, Error> {
+        self.0.end().map(Value::Json)
+    }
+}
+
+impl<S: SerializeStruct<Ok = JsonValue, Error = Error>> SerializeStruct for Json<S> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> std::result::Result<(), Error> {
+        self.0.serialize_field(key, value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> std::result::Result<(), Error> {
+        self.0.skip_field(key)
+    }
+
+    fn end(self) -> std::result::Result<Value, Error> {
+        self.0.end().map(Value::Json)
+    }
+}
+
+impl<S: ser::SerializeStructVariant<Ok = JsonValue, Error = Error>> ser::SerializeStructVariant for Json<S> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> std::result::Result<(), Error> {
+        self.0.serialize_field(key, value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> std::result::Result<(), Error> {
+        self.0.skip_field(key)
+    }
+
+    fn end(self) -> std::result::Result<Value, Error> {
+        self.0.end().map(Value::Json)
+    }
+}
+
+// Accepts exactly a u8, to recognize byte sequences
+struct ByteSerializer;
+
+fn not_a_byte<T>() -> std::result::Result<T, Error> {
+    Err(Error::custom("not a byte"))
+}
+
+impl ser::Serializer for ByteSerializer {
+    type Ok = u8;
+    type Error = Error;
+    type SerializeSeq = Impossible<u8, Error>;
+    type SerializeTuple = Impossible<u8, Error>;
+    type SerializeTupleStruct = Impossible<u8, Error>;
+    type SerializeTupleVariant = Impossible<u8, Error>;
+    type SerializeMap = Impossible<u8, Error>;
+    type SerializeStruct = Impossible<u8, Error>;
+    type SerializeStructVariant = Impossible<u8, Error>;
+
+    fn serialize_u8(self, v: u8) -> std::result::Result<u8, Error> {
+        Ok(v)
+    }
+
+    fn serialize_bool(self, _v: bool) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_i8(self, _v: i8) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_i16(self, _v: i16) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_i32(self, _v: i32) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_i64(self, _v: i64) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_u16(self, _v: u16) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_u32(self, _v: u32) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_u64(self, _v: u64) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_f32(self, _v: f32) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_f64(self, _v: f64) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_char(self, _v: char) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_str(self, _v: &str) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_none(self) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_unit(self) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, _variant: &'static str) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, _value: &T) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> std::result::Result<u8, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> std::result::Result<Self::SerializeSeq, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_tuple(self, _len: usize) -> std::result::Result<Self::SerializeTuple, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> std::result::Result<Self::SerializeTupleStruct, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeTupleVariant, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> std::result::Result<Self::SerializeMap, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> std::result::Result<Self::SerializeStruct, Error> {
+        not_a_byte()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> std::result::Result<Self::SerializeStructVariant, Error> {
+        not_a_byte()
+    }
+}
@@ -0,0 +1,243 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use sqlx::pool::PoolConnection;
+use sqlx::{Sqlite, SqliteConnection};
+
+use crate::batch::Batch;
+use crate::error::{DatabaseError, Result};
+use crate::executor;
+use crate::migration::BoxFuture;
+use crate::script;
+
+/// How `BEGIN` acquires locks. See <https://www.sqlite.org/lang_transaction.html>.
+///
+/// `Deferred` takes the write lock on the first write, which can fail with `SQLITE_BUSY` if
+/// another connection got there first. `Immediate` takes it up front, so read-then-write
+/// transactions either start or wait for the busy timeout.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransactionBehavior {
+    #[default]
+    Deferred,
+    Immediate,
+    Exclusive,
+}
+
+impl TransactionBehavior {
+    fn begin_sql(self) -> &'static str {
+        match self {
+            TransactionBehavior::Deferred => "BEGIN DEFERRED",
+            TransactionBehavior::Immediate => "BEGIN IMMEDIATE",
+            TransactionBehavior::Exclusive => "BEGIN EXCLUSIVE",
+        }
+    }
+}
+
+/// An open transaction on a pooled connection.
+///
+/// Finish it with [`commit`](Transaction::commit) or [`rollback`](Transaction::rollback).
+/// A transaction dropped without either is rolled back before its connection returns to
+/// the pool.
+///
+/// Code that needs its own atomic scope while running inside a caller's transaction should
+/// take `&mut Transaction` and use [`Transaction::transaction`], which nests via SAVEPOINT.
+pub struct Transaction {
+    conn: Option<PoolConnection<Sqlite>>,
+    behavior: TransactionBehavior,
+    scopes: Arc<Scopes>,
+}
+
+// Shared with the guards of running nested scopes, which can only record a cancelled scope
+// when their future is dropped; the savepoint is rolled back before the next statement
+#[derive(Debug, Default)]
+struct Scopes {
+    depth: AtomicUsize,
+    // The outermost savepoint left open by a cancelled scope, 0 if none
+    abandoned: AtomicUsize,
+}
+
+impl Scopes {
+    fn abandon(&self, depth: usize) {
+        let _ = self
+            .abandoned
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |a| (a == 0 || a > depth).then_some(depth));
+    }
+}
+
+struct ScopeGuard {
+    scopes: Arc<Scopes>,
+    depth: usize,
+}
+
+impl ScopeGuard {
+    fn finish(self) {
+        self.scopes.depth.store(self.depth - 1, Ordering::SeqCst);
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        self.scopes.depth.store(self.depth - 1, Ordering::SeqCst);
+        self.scopes.abandon(self.depth);
+    }
+}
+
+// Runs a query helper's future on the open connection; see executor::query_helpers
+macro_rules! on_connection {
+    ($tx:ident, |$exec:ident| $future:expr) => {{
+        let $exec = $tx.active().await?;
+        $future.await
+    }};
+    (stream $tx:ident, |$exec:ident| $future:expr) => {{
+        let $exec = $tx.active().await?;
+        $future
+    }};
+}
+
+fn savepoint_name(depth: usize) -> String {
+    format!("burncloud_savepoint_{}", depth)
+}
+
+impl Transaction {
+    pub(crate) async fn begin(mut conn: PoolConnection<Sqlite>, behavior: TransactionBehavior) -> Result<Self> {
+        if let Err(e) = sqlx::query(behavior.begin_sql()).execute(&mut *conn).await {
+            // After a BEGIN fails with SQLITE_BUSY the connection keeps failing later BEGINs
+            // until it runs another statement, so run a (usually no-op) ROLLBACK before it
+            // goes back to the pool
+            let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+            return Err(e.into());
+        }
+        Ok(Self {
+            conn: Some(conn),
+            behavior,
+            scopes: Arc::default(),
+        })
+    }
+
+    pub fn behavior(&self) -> TransactionBehavior {
+        self.behavior
+    }
+
+    /// Number of savepoints currently open; 0 outside any nested scope.
+    pub fn depth(&self) -> usize {
+        self.scopes.depth.load(Ordering::SeqCst)
+    }
+
+    /// The underlying connection, for running raw sqlx queries inside the transaction.
+    pub fn connection(&mut self) -> Result<&mut SqliteConnection> {
+        self.conn.as_deref_mut().ok_or(DatabaseError::NotInitialized)
+    }
+
+    // The connection, after rolling back any savepoint left open by a cancelled scope
+    async fn active(&mut self) -> Result<&mut SqliteConnection> {
+        let abandoned = self.scopes.abandoned.swap(0, Ordering::SeqCst);
+        if abandoned != 0 {
+            let savepoint = savepoint_name(abandoned);
+            let conn = self.connection()?;
+            // ROLLBACK TO keeps the savepoint open, so it is released afterwards
+            let result = match executor::execute(&mut *conn, &format!("ROLLBACK TO {}", savepoint), None).await {
+                Ok(_) => executor::execute(&mut *conn, &format!("RELEASE {}", savepoint), None).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                self.scopes.abandon(abandoned);
+                return Err(e);
+            }
+        }
+        self.connection()
+    }
+
+    pub async fn commit(mut self) -> Result<()> {
+        self.active().await?;
+        let mut conn = self.conn.take().ok_or(DatabaseError::NotInitialized)?;
+        if let Err(e) = sqlx::query("COMMIT").execute(&mut *conn).await {
+            // A failed COMMIT (e.g. SQLITE_BUSY) leaves the transaction open
+            let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    pub async fn rollback(mut self) -> Result<()> {
+        let mut conn = self.conn.take().ok_or(DatabaseError::NotInitialized)?;
+        sqlx::query("ROLLBACK").execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    /// Runs `f` in a nested scope backed by a SAVEPOINT.
+    ///
+    /// On `Ok` the savepoint is released into the enclosing transaction; on `Err` only the
+    /// nested work is rolled back and the enclosing transaction stays usable. Nothing is
+    /// durable until the outermost transaction commits. If the returned future is dropped
+    /// before `f` finishes (e.g. by a timeout), the nested work is rolled back before the
+    /// transaction runs its next statement.
+    pub async fn transaction<F, R>(&mut self, f: F) -> Result<R>
+    where
+        F: for<'t> FnOnce(&'t mut Transaction) -> BoxFuture<'t, Result<R>>,
+    {
+        let depth = self.depth() + 1;
+        let savepoint = savepoint_name(depth);
+        executor::execute(self.active().await?, &format!("SAVEPOINT {}", savepoint), None).await?;
+        self.scopes.depth.store(depth, Ordering::SeqCst);
+
+        // Restores the depth and marks the savepoint for rollback if this future is dropped
+        // before `f` finishes
+        let guard = ScopeGuard {
+            scopes: Arc::clone(&self.scopes),
+            depth,
+        };
+        let result = f(self).await;
+        guard.finish();
+
+        match result {
+            Ok(value) => {
+                executor::execute(self.active().await?, &format!("RELEASE {}", savepoint), None).await?;
+                Ok(value)
+            }
+            Err(e) => {
+                // ROLLBACK TO keeps the savepoint open, so it is released afterwards
+                if let Ok(conn) = self.active().await {
+                    let _ = executor::execute(&mut *conn, &format!("ROLLBACK TO {}", savepoint), None).await;
+                    let _ = executor::execute(&mut *conn, &format!("RELEASE {}", savepoint), None).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs a multi-statement SQL script in a nested scope; see [`crate::Database::execute_script`].
+    pub async fn execute_script(&mut self, sql: &str) -> Result<usize> {
+        let sql = sql.to_string();
+        self.transaction(move |tx| Box::pin(async move { script::run(tx, &sql).await })).await
+    }
+
+    // Runs the batch in a nested scope, so a failed batch leaves the transaction usable
+    async fn run_batch(&mut self, batch: Batch) -> Result<u64> {
+        if batch.is_empty() {
+            return Ok(0);
+        }
+        self.transaction(move |tx| Box::pin(async move { batch.run(tx.connection()?).await })).await
+    }
+
+    executor::query_helpers!(on_connection, mut);
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        // Dropped mid-transaction (early return, panic or a cancelled future): roll back on
+        // a background task and only then release the connection to the pool
+        if let Some(mut conn) = self.conn.take() {
+            match tokio::runtime::Handle::try_current() {
+                Ok(handle) => {
+                    handle.spawn(async move {
+                        let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+                    });
+                }
+                // Without a runtime to roll back on, close the connection instead of returning
+                // it to the pool; SQLite rolls back on close
+                Err(_) => drop(conn.detach()),
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+use crate::batch::quote_identifier;
+use crate::error::{DatabaseError, Result};
+use crate::ser::record_fields;
+use crate::value::Value;
+
+/// How an upsert merges one column of an existing row with the incoming value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeRule {
+    /// Take the incoming value.
+    #[default]
+    Overwrite,
+    /// Leave the stored value untouched.
+    KeepExisting,
+    /// Add the incoming value to the stored one, treating a stored NULL as 0.
+    Increment,
+    /// Keep the larger of the two values, ignoring NULLs.
+    Max,
+    /// Keep the smaller of the two values, ignoring NULLs.
+    Min,
+}
+
+impl MergeRule {
+    fn set_sql(self, column: &str) -> Option<String> {
+        let excluded = format!("excluded.{}", column);
+        match self {
+            MergeRule::Overwrite => Some(excluded),
+            MergeRule::KeepExisting => None,
+            MergeRule::Increment => Some(format!("coalesce({}, 0) + {}", column, excluded)),
+            // SQLite's max()/min() return NULL if any argument is NULL
+            MergeRule::Max => Some(format!("max(coalesce({c}, {e}), coalesce({e}, {c}))", c = column, e = excluded)),
+            MergeRule::Min => Some(format!("min(coalesce({c}, {e}), coalesce({e}, {c}))", c = column, e = excluded)),
+        }
+    }
+}
+
+/// Per-column merge rules for [`crate::Database::upsert`].
+///
+/// Columns without a rule use the default rule, which is `Overwrite` unless built with
+/// [`UpdatePolicy::keep_existing`]. Conflict target columns are never updated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdatePolicy {
+    default: MergeRule,
+    rules: Vec<(String, MergeRule)>,
+}
+
+impl UpdatePolicy {
+    pub fn overwrite() -> Self {
+        Self::default()
+    }
+
+    pub fn keep_existing() -> Self {
+        Self {
+            default: MergeRule::KeepExisting,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, column: impl Into<String>, rule: MergeRule) -> Self {
+        let column = column.into();
+        self.rules.retain(|(name, _)| *name != column);
+        self.rules.push((column, rule));
+        self
+    }
+
+    pub fn rule(&self, column: &str) -> MergeRule {
+        self.rules
+            .iter()
+            .find(|(name, _)| name == column)
+            .map_or(self.default, |(_, rule)| *rule)
+    }
+}
+
+// Builds `INSERT ... ON CONFLICT (...) DO UPDATE SET ...` for one record
+pub(crate) fn upsert_sql<T: Serialize + ?Sized>(
+    table: &str,
+    record: &T,
+    conflict_columns: &[&str],
+    policy: &UpdatePolicy,
+) -> Result<(String, Vec<Value>)> {
+    let fields = record_fields(record, 0)?;
+    if conflict_columns.is_empty() {
+        return Err(DatabaseError::InvalidData {
+            message: "upsert needs at least one conflict column".to_string(),
+        });
+    }
+    if let Some(missing) = conflict_columns.iter().find(|c| !fields.iter().any(|(name, _)| name == *c)) {
+        return Err(DatabaseError::InvalidData {
+            message: format!("conflict column {} is not a field of the record", missing),
+        });
+    }
+
+    let columns: Vec<String> = fields.iter().map(|(c, _)| quote_identifier(c)).collect();
+    let updates: Vec<String> = fields
+        .iter()
+        .map(|(c, _)| c)
+        .filter(|c| !conflict_columns.contains(&c.as_str()))
+        .filter_map(|c| {
+            let column = quote_identifier(c);
+            policy.rule(c).set_sql(&column).map(|value| format!("{} = {}", column, value))
+        })
+        .collect();
+
+    let action = if updates.is_empty() {
+        "DO NOTHING".to_string()
+    } else {
+        format!("DO UPDATE SET {}", updates.join(", "))
+    };
+    let sql = format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) {}",
+        quote_identifier(table),
+        columns.join(", "),
+        vec!["?"; columns.len()].join(", "),
+        conflict_columns.iter().map(|c| quote_identifier(c)).collect::<Vec<_>>().join(", "),
+        action
+    );
+    let values = fields.into_iter().map(|(_, value)| value).collect();
+    Ok((sql, values))
+}
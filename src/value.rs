@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::encode::IsNull;
+use sqlx::sqlite::{SqliteArgumentValue, SqliteArguments, SqliteTypeInfo};
+use sqlx::{Arguments, Encode, Sqlite, Type};
+use uuid::Uuid;
+
+/// A dynamically typed bind parameter.
+///
+/// `Bool` binds as an integer (0/1), `Json` as its text serialization, `Uuid` as hyphenated
+/// text and `DateTime` in sqlx's SQLite text format.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+    Bool(bool),
+    Json(serde_json::Value),
+    Uuid(Uuid),
+    DateTime(DateTime<Utc>),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Serializes `value` to JSON text, or NULL if it serializes to `null`.
+    pub fn json<T: Serialize + ?Sized>(value: &T) -> crate::error::Result<Self> {
+        match serde_json::to_value(value)? {
+            serde_json::Value::Null => Ok(Value::Null),
+            json => Ok(Value::Json(json)),
+        }
+    }
+
+    // Type and size only, for error messages that must not leak values
+    pub(crate) fn redacted(&self) -> String {
+        match self {
+            Value::Null => "null".to_string(),
+            Value::Integer(_) => "integer".to_string(),
+            Value::Real(_) => "real".to_string(),
+            Value::Text(text) => format!("text({} chars)", text.chars().count()),
+            Value::Blob(bytes) => format!("blob({} bytes)", bytes.len()),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Json(_) => "json".to_string(),
+            Value::Uuid(_) => "uuid".to_string(),
+            Value::DateTime(_) => "datetime".to_string(),
+        }
+    }
+
+    // Scalars map onto their natural variant; arrays and objects stay JSON
+    pub(crate) fn from_json_field(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(value) => Value::Bool(value),
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(value) => Value::Integer(value),
+                None => Value::Real(number.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(text) => Value::Text(text),
+            other => Value::Json(other),
+        }
+    }
+}
+
+/// Builds a `Vec<Value>` from heterogeneous expressions.
+///
+/// ```
+/// use burncloud_database::{params, Value};
+///
+/// let params = params![42, "llama", 0.5, None::<i64>, true];
+/// assert_eq!(params[0], Value::Integer(42));
+/// assert_eq!(params[3], Value::Null);
+/// ```
+#[macro_export]
+macro_rules! params {
+    () => {
+        ::std::vec::Vec::<$crate::Value>::new()
+    };
+    ($($value:expr),+ $(,)?) => {
+        ::std::vec![$($crate::Value::from($value)),+]
+    };
+}
+
+macro_rules! impl_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Value::Integer(i64::from(value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_integer!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Real(f64::from(value))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Real(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<&String> for Value {
+    fn from(value: &String) -> Self {
+        Value::Text(value.clone())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Blob(value)
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(value: &[u8]) -> Self {
+        Value::Blob(value.to_vec())
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        Value::Json(value)
+    }
+}
+
+impl From<Uuid> for Value {
+    fn from(value: Uuid) -> Self {
+        Value::Uuid(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(value: DateTime<Utc>) -> Self {
+        Value::DateTime(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl Type<Sqlite> for Value {
+    // The nominal type; the per-value type is reported by `Encode::produces`
+    fn type_info() -> SqliteTypeInfo {
+        <str as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(_ty: &SqliteTypeInfo) -> bool {
+        true
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Value {
+    fn encode(self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        match self {
+            Value::Text(text) => {
+                buf.push(SqliteArgumentValue::Text(Cow::Owned(text)));
+                IsNull::No
+            }
+            Value::Blob(bytes) => {
+                buf.push(SqliteArgumentValue::Blob(Cow::Owned(bytes)));
+                IsNull::No
+            }
+            other => other.encode_by_ref(buf),
+        }
+    }
+
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> IsNull {
+        match self {
+            Value::Null => return IsNull::Yes,
+            Value::Integer(value) => buf.push(SqliteArgumentValue::Int64(*value)),
+            Value::Real(value) => buf.push(SqliteArgumentValue::Double(*value)),
+            Value::Text(text) => buf.push(SqliteArgumentValue::Text(Cow::Owned(text.clone()))),
+            Value::Blob(bytes) => buf.push(SqliteArgumentValue::Blob(Cow::Owned(bytes.clone()))),
+            Value::Bool(value) => buf.push(SqliteArgumentValue::Int64(i64::from(*value))),
+            Value::Json(json) => buf.push(SqliteArgumentValue::Text(Cow::Owned(json.to_string()))),
+            Value::Uuid(uuid) => buf.push(SqliteArgumentValue::Text(Cow::Owned(uuid.hyphenated().to_string()))),
+            Value::DateTime(datetime) => return Encode::<Sqlite>::encode_by_ref(datetime, buf),
+        }
+        IsNull::No
+    }
+
+    fn produces(&self) -> Option<SqliteTypeInfo> {
+        Some(match self {
+            Value::Null => return None,
+            Value::Integer(_) => <i64 as Type<Sqlite>>::type_info(),
+            Value::Real(_) => <f64 as Type<Sqlite>>::type_info(),
+            Value::Blob(_) => <[u8] as Type<Sqlite>>::type_info(),
+            Value::Bool(_) => <bool as Type<Sqlite>>::type_info(),
+            Value::DateTime(_) => <DateTime<Utc> as Type<Sqlite>>::type_info(),
+            Value::Text(_) | Value::Json(_) | Value::Uuid(_) => <str as Type<Sqlite>>::type_info(),
+        })
+    }
+}
+
+pub(crate) fn into_arguments<'q, P>(params: P) -> SqliteArguments<'q>
+where
+    P: IntoIterator,
+    P::Item: Into<Value>,
+{
+    let mut arguments = SqliteArguments::default();
+    for param in params {
+        arguments.add(param.into());
+    }
+    arguments
+}
@@ -2,7 +2,6 @@ use burncloud_database::{
     Database, DatabaseError,
     create_default_database
 };
-use tempfile::TempDir;
 
 /// API compatibility and regression tests
 /// These tests ensure backward compatibility and API consistency
@@ -13,8 +12,7 @@ async fn test_database_creation_methods() {
 
     // Method 1: Database::new() - creates initialized database with default path
     let db_result = Database::new().await;
-    if db_result.is_ok() {
-        let db = db_result.unwrap();
+    if let Ok(db) = db_result {
         assert!(db.connection().is_ok(), "Default database should be initialized");
         let _ = db.close().await;
     }
@@ -22,8 +20,7 @@ async fn test_database_creation_methods() {
     // Method 2: Using default database (custom paths no longer supported through new_with_path)
     // Testing with default database instead
     let default_db_result = Database::new().await;
-    if default_db_result.is_ok() {
-        let explicit_db = default_db_result.unwrap();
+    if let Ok(explicit_db) = default_db_result {
         assert!(explicit_db.connection().is_ok(), "Default database should be initialized");
         let _ = explicit_db.close().await;
     }
@@ -66,6 +63,7 @@ async fn test_database_operation_consistency() {
 
         // Test fetch_one
         #[derive(sqlx::FromRow)]
+        #[allow(dead_code)]
         struct ApiTestRow {
             id: i64,
             name: String,
@@ -114,11 +112,11 @@ async fn test_error_type_consistency() {
     // Test that all database creation methods return consistent error types
 
     // Test with invalid paths
-    let invalid_path = "/definitely/invalid/path/test.db";
+    let _invalid_path = "/definitely/invalid/path/test.db";
 
     // Test with default database instead of invalid path
     // Since new_with_path is removed, test error handling with default database
-    let default_db_result = Database::new().await;
+    let _default_db_result = Database::new().await;
     // Default database creation might fail in test environments, which is acceptable
 
     // Both should return DatabaseError for invalid operations
@@ -151,8 +149,7 @@ async fn test_backward_compatibility() {
     // Pattern 1: Using default database (custom paths no longer supported)
     // Test with default database instead
     let default_db_result = Database::new().await;
-    if default_db_result.is_ok() {
-        let path_db = default_db_result.unwrap();
+    if let Ok(path_db) = default_db_result {
         // Should work as before
         let result = path_db.execute_query("CREATE TABLE test (id INTEGER)").await;
         if result.is_ok() {
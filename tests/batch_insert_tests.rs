@@ -0,0 +1,161 @@
+use burncloud_database::{params, quote_identifier, DatabaseBuilder, DatabaseError, ErrorKind, OnConflict, Value};
+use serde::Serialize;
+
+// Tests for chunked multi-row inserts
+
+#[tokio::test]
+async fn test_insert_many_chunks_large_batches() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query("CREATE TABLE usage_logs (id INTEGER PRIMARY KEY, model TEXT NOT NULL, tokens INTEGER NOT NULL)")
+        .await
+        .unwrap();
+
+    // 50,000 rows of 3 values is well past the 32,766 bound-parameter limit
+    let rows = (0..50_000_i64).map(|i| vec![Value::from(i + 1), Value::from(format!("model-{}", i % 7)), Value::from(i)]);
+    let inserted = db.insert_many("usage_logs", &["id", "model", "tokens"], rows).await.unwrap();
+    assert_eq!(inserted, 50_000);
+
+    let (count, total): (i64, i64) = db.fetch_one("SELECT COUNT(*), SUM(tokens) FROM usage_logs").await.unwrap();
+    assert_eq!((count, total), (50_000, 49_999 * 50_000 / 2));
+
+    assert_eq!(db.insert_many("usage_logs", &["model"], Vec::<Vec<Value>>::new()).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_insert_many_conflicts_and_errors() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query("CREATE TABLE \"model tags\" (model TEXT NOT NULL, tag TEXT NOT NULL, weight REAL, UNIQUE (model, tag))")
+        .await
+        .unwrap();
+    let columns = ["model", "tag", "weight"];
+    db.insert_many("model tags", &columns, [params!["llama", "chat", 1.0]]).await.unwrap();
+
+    // A conflict in any row aborts the whole batch
+    let err = db
+        .insert_many("model tags", &columns, [params!["gpt", "chat", 1.0], params!["llama", "chat", 2.0]])
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+    let count: i64 = db.fetch_scalar("SELECT COUNT(*) FROM \"model tags\"").await.unwrap();
+    assert_eq!(count, 1);
+
+    let batch = || [params!["gpt", "chat", 1.0], params!["llama", "chat", 2.0]];
+    assert_eq!(db.insert_many_with(OnConflict::Ignore, "model tags", &columns, batch()).await.unwrap(), 1);
+    let weight: f64 = db.fetch_scalar("SELECT weight FROM \"model tags\" WHERE model = 'llama'").await.unwrap();
+    assert_eq!(weight, 1.0);
+
+    assert_eq!(db.insert_many_with(OnConflict::Replace, "model tags", &columns, batch()).await.unwrap(), 2);
+    let weight: f64 = db.fetch_scalar("SELECT weight FROM \"model tags\" WHERE model = 'llama'").await.unwrap();
+    assert_eq!(weight, 2.0);
+
+    let err = db.insert_many("model tags", &columns, [params!["short"]]).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+    let err = db.insert_many("model tags", &[], [Vec::<Value>::new()]).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+
+    assert_eq!(quote_identifier("model tags"), "\"model tags\"");
+    assert_eq!(quote_identifier("we\"ird"), "\"we\"\"ird\"");
+}
+
+#[derive(Serialize)]
+struct UsageLog {
+    model: String,
+    tokens: i64,
+    cached: bool,
+    metadata: serde_json::Value,
+    region: Option<String>,
+}
+
+#[tokio::test]
+async fn test_insert_records_from_serialize() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE usage_logs (id INTEGER PRIMARY KEY, model TEXT, tokens INTEGER, cached BOOLEAN, metadata TEXT, region TEXT)",
+    )
+    .await
+    .unwrap();
+
+    let logs: Vec<UsageLog> = (0..1_000)
+        .map(|i| UsageLog {
+            model: format!("model-{}", i % 3),
+            tokens: i,
+            cached: i % 2 == 0,
+            metadata: serde_json::json!({ "request": i }),
+            region: (i % 10 == 0).then(|| "eu".to_string()),
+        })
+        .collect();
+    assert_eq!(db.insert_records("usage_logs", &logs).await.unwrap(), 1_000);
+
+    let row: (String, i64, bool, String, Option<String>) = db
+        .fetch_one("SELECT model, tokens, cached, metadata, region FROM usage_logs WHERE tokens = 10")
+        .await
+        .unwrap();
+    assert_eq!(row, ("model-1".to_string(), 10, true, "{\"request\":10}".to_string(), Some("eu".to_string())));
+
+    let err = db.insert_records("usage_logs", [1, 2]).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+
+    // Inside a transaction a failed batch only undoes itself
+    let mut tx = db.begin().await.unwrap();
+    tx.insert_many("usage_logs", &["id", "model"], [params![5_000, "kept"]]).await.unwrap();
+    let err = tx
+        .insert_many("usage_logs", &["id", "model"], [params![5_001, "dropped"], params![1, "duplicate"]])
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+    assert_eq!(tx.insert_records_with(OnConflict::Ignore, "usage_logs", &logs[..5]).await.unwrap(), 5);
+    tx.commit().await.unwrap();
+
+    let models: Vec<(String,)> = db.fetch_all("SELECT model FROM usage_logs WHERE model IN ('kept', 'dropped', 'duplicate')").await.unwrap();
+    assert_eq!(models, vec![("kept".to_string(),)]);
+}
+
+#[derive(Serialize)]
+struct Artifact {
+    id: i64,
+    data: Vec<u8>,
+    digest: [u8; 4],
+    at: chrono::DateTime<chrono::Utc>,
+    tags: Vec<String>,
+    empty: Vec<u8>,
+}
+
+#[tokio::test]
+async fn test_insert_records_binds_fields_like_params() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query("CREATE TABLE artifacts (id INTEGER PRIMARY KEY, data BLOB, digest BLOB, at TEXT, tags TEXT, empty BLOB)")
+        .await
+        .unwrap();
+
+    let at = chrono::DateTime::parse_from_rfc3339("2024-06-01T12:30:00Z").unwrap().with_timezone(&chrono::Utc);
+    let artifact = |id| Artifact {
+        id,
+        data: vec![1, 2, 3],
+        digest: [0xde, 0xad, 0xbe, 0xef],
+        at,
+        tags: vec!["a".to_string()],
+        empty: Vec::new(),
+    };
+    db.insert_records("artifacts", [artifact(1)]).await.unwrap();
+    db.upsert("artifacts", &artifact(2), &["id"], &Default::default()).await.unwrap();
+    db.insert_many(
+        "artifacts",
+        &["id", "data", "digest", "at", "tags", "empty"],
+        [params![3, vec![1_u8, 2, 3], vec![0xde_u8, 0xad, 0xbe, 0xef], at, "[\"a\"]", "[]"]],
+    )
+    .await
+    .unwrap();
+
+    let types: Vec<(String, String, String, String, String)> = db
+        .fetch_all("SELECT typeof(data), typeof(digest), typeof(at), tags, empty FROM artifacts ORDER BY id")
+        .await
+        .unwrap();
+    let expected = ("blob".to_string(), "blob".to_string(), "text".to_string(), "[\"a\"]".to_string(), "[]".to_string());
+    assert_eq!(types, vec![expected; 3]);
+
+    let ids: Vec<(i64,)> = db
+        .fetch_all_with_params("SELECT id FROM artifacts WHERE at = ? AND data = ? ORDER BY id", params![at, vec![1_u8, 2, 3]])
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![(1,), (2,), (3,)]);
+}
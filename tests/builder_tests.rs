@@ -0,0 +1,119 @@
+use burncloud_database::{create_in_memory_database, Database, DatabaseBuilder, DatabaseConnection, DatabaseError};
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Tests for DatabaseBuilder: explicit paths, in-memory databases and pool configuration
+
+#[tokio::test]
+async fn test_builder_with_explicit_path() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("nested").join("dir").join("builder.db");
+
+    let db = DatabaseBuilder::new()
+        .path(&db_path)
+        .build()
+        .await
+        .expect("Builder should create the database and its directories");
+
+    assert_eq!(db.database_path(), db_path.to_string_lossy());
+    assert!(!db.is_in_memory());
+    assert!(db_path.exists(), "Database file should be created");
+
+    db.execute_query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)").await.unwrap();
+    db.execute_query("INSERT INTO items (name) VALUES ('persisted')").await.unwrap();
+    db.close().await.unwrap();
+
+    // Reopening the same path sees the same data
+    let db = Database::builder().path(&db_path).build().await.unwrap();
+    let rows: Vec<(i64, String)> = db.fetch_all("SELECT id, name FROM items").await.unwrap();
+    assert_eq!(rows, vec![(1, "persisted".to_string())]);
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_builder_without_create_if_missing() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("missing.db");
+
+    let result = DatabaseBuilder::new().path(&db_path).create_if_missing(false).build().await;
+    assert!(matches!(result, Err(DatabaseError::Connection(_))));
+    assert!(!db_path.exists(), "Database file should not be created");
+}
+
+#[tokio::test]
+async fn test_in_memory_databases_are_isolated_and_shared_across_pool() {
+    let first = DatabaseBuilder::new().in_memory().max_connections(4).build().await.unwrap();
+    let second = create_in_memory_database().await.unwrap();
+
+    assert!(first.is_in_memory());
+    assert_eq!(first.database_path(), ":memory:");
+
+    first.execute_query("CREATE TABLE shared (value INTEGER)").await.unwrap();
+
+    // Every pooled connection must see the same in-memory database
+    let mut handles = Vec::new();
+    for i in 0..8 {
+        let connection = first.connection().unwrap().clone();
+        handles.push(tokio::spawn(async move {
+            sqlx::query("INSERT INTO shared (value) VALUES (?)")
+                .bind(i)
+                .execute(connection.pool())
+                .await
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+    let count: (i64,) = first.fetch_one("SELECT COUNT(*) FROM shared").await.unwrap();
+    assert_eq!(count.0, 8);
+
+    // A second in-memory database does not see the first one's tables
+    assert!(second.execute_query("SELECT * FROM shared").await.is_err());
+
+    first.close().await.unwrap();
+    second.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pool_configuration() {
+    let db = DatabaseBuilder::new()
+        .in_memory()
+        .min_connections(2)
+        .max_connections(3)
+        .acquire_timeout(Duration::from_secs(1))
+        .idle_timeout(Some(Duration::from_secs(30)))
+        .build()
+        .await
+        .unwrap();
+
+    let pool = db.connection().unwrap().pool();
+    assert_eq!(pool.options().get_max_connections(), 3);
+    assert_eq!(pool.options().get_min_connections(), 2);
+    assert_eq!(pool.options().get_acquire_timeout(), Duration::from_secs(1));
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_connection_from_url_uses_builder_defaults() {
+    let temp_dir = TempDir::new().unwrap();
+    let url = format!("sqlite://{}?mode=rwc", temp_dir.path().join("url.db").display());
+    let conn = DatabaseConnection::new(&url).await.unwrap();
+
+    let pool = conn.pool();
+    assert_eq!(pool.options().get_acquire_timeout(), Duration::from_secs(30));
+    assert_eq!(pool.options().get_idle_timeout(), Some(Duration::from_secs(10 * 60)));
+    let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(pool).await.unwrap();
+    assert_eq!(foreign_keys, 1);
+
+    conn.close().await;
+}
+
+#[tokio::test]
+async fn test_invalid_pool_size_rejected() {
+    let zero = DatabaseBuilder::new().in_memory().max_connections(0).build().await;
+    assert!(matches!(zero, Err(DatabaseError::InvalidData { .. })));
+
+    let inverted = DatabaseBuilder::new().in_memory().min_connections(5).max_connections(2).build().await;
+    assert!(matches!(inverted, Err(DatabaseError::InvalidData { .. })));
+}
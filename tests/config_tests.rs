@@ -0,0 +1,142 @@
+use burncloud_database::config::config_file_path;
+use burncloud_database::{
+    Database, DatabaseBuilder, DatabaseConfig, DatabaseError, CONFIG_ENV, DATABASE_URL_ENV, HOME_ENV,
+};
+use std::fs;
+use tempfile::TempDir;
+
+// Tests for environment and burncloud.toml overrides of the database location
+// Environment variables are process-wide, so every scenario that touches them runs in one test
+
+#[test]
+fn test_parse_database_config() {
+    let config = DatabaseConfig::from_toml(
+        r#"
+        [server]
+        port = 8080
+
+        [database]
+        path = "/var/lib/burncloud/data.db"
+        min_connections = 1
+        max_connections = 8
+        acquire_timeout_secs = 10
+        idle_timeout_secs = 60
+        create_if_missing = false
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.path.as_deref(), Some(std::path::Path::new("/var/lib/burncloud/data.db")));
+    assert_eq!(config.min_connections, Some(1));
+    assert_eq!(config.max_connections, Some(8));
+    assert_eq!(config.acquire_timeout_secs, Some(10));
+    assert_eq!(config.idle_timeout_secs, Some(60));
+    assert_eq!(config.create_if_missing, Some(false));
+
+    assert_eq!(DatabaseConfig::from_toml("").unwrap(), DatabaseConfig::default());
+}
+
+#[test]
+fn test_invalid_database_config() {
+    let unknown_key = DatabaseConfig::from_toml("[database]\nmax_conections = 4\n");
+    assert!(matches!(unknown_key, Err(DatabaseError::Configuration(_))));
+
+    let wrong_type = DatabaseConfig::from_toml("[database]\nmax_connections = \"four\"\n");
+    assert!(matches!(wrong_type, Err(DatabaseError::Configuration(_))));
+}
+
+#[test]
+fn test_relative_config_path_resolved_against_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("burncloud.toml");
+    fs::write(&config_path, "[database]\npath = \"db/data.db\"\n").unwrap();
+
+    let config = DatabaseConfig::from_file(&config_path).unwrap();
+    assert_eq!(config.path, Some(temp_dir.path().join("db").join("data.db")));
+}
+
+fn clear_env() {
+    for key in [DATABASE_URL_ENV, HOME_ENV, CONFIG_ENV] {
+        std::env::remove_var(key);
+    }
+}
+
+#[tokio::test]
+async fn test_environment_precedence() {
+    clear_env();
+    let temp_dir = TempDir::new().unwrap();
+
+    // Config file alone sets the path and pool options
+    let config_path = temp_dir.path().join("custom.toml");
+    let config_db = temp_dir.path().join("from_config.db");
+    fs::write(
+        &config_path,
+        format!("[database]\npath = {:?}\nmax_connections = 3\n", config_db.to_string_lossy()),
+    )
+    .unwrap();
+    std::env::set_var(CONFIG_ENV, &config_path);
+
+    let db = Database::new().await.unwrap();
+    assert_eq!(db.database_path(), config_db.to_string_lossy());
+    assert_eq!(db.connection().unwrap().pool().options().get_max_connections(), 3);
+    db.close().await.unwrap();
+
+    // BURNCLOUD_HOME beats the config file's path but keeps its pool options
+    let home = temp_dir.path().join("home");
+    std::env::set_var(HOME_ENV, &home);
+    let db = Database::new().await.unwrap();
+    assert_eq!(db.database_path(), home.join("data.db").to_string_lossy());
+    assert_eq!(db.connection().unwrap().pool().options().get_max_connections(), 3);
+    db.close().await.unwrap();
+
+    // BURNCLOUD_DATABASE_URL beats everything
+    let url_db = temp_dir.path().join("from_url.db");
+    std::env::set_var(DATABASE_URL_ENV, format!("sqlite://{}", url_db.display()));
+    let db = Database::new().await.unwrap();
+    assert_eq!(db.database_path(), url_db.to_string_lossy());
+    db.close().await.unwrap();
+
+    std::env::set_var(DATABASE_URL_ENV, "sqlite::memory:");
+    let db = Database::new().await.unwrap();
+    assert!(db.is_in_memory());
+    db.close().await.unwrap();
+
+    // Explicit builder settings beat the environment
+    let explicit_db = temp_dir.path().join("explicit.db");
+    let db = DatabaseBuilder::from_env().unwrap().path(&explicit_db).build().await.unwrap();
+    assert_eq!(db.database_path(), explicit_db.to_string_lossy());
+    db.close().await.unwrap();
+
+    // Without BURNCLOUD_CONFIG, burncloud.toml is read from BURNCLOUD_HOME
+    clear_env();
+    fs::create_dir_all(&home).unwrap();
+    fs::write(home.join("burncloud.toml"), "[database]\nmax_connections = 2\n").unwrap();
+    std::env::set_var(HOME_ENV, &home);
+    let db = Database::new().await.unwrap();
+    assert_eq!(db.connection().unwrap().pool().options().get_max_connections(), 2);
+    db.close().await.unwrap();
+
+    // Otherwise it comes from the config directory, not the data directory
+    #[cfg(target_os = "linux")]
+    {
+        clear_env();
+        std::env::set_var("XDG_CONFIG_HOME", temp_dir.path().join("config"));
+        std::env::set_var("XDG_DATA_HOME", temp_dir.path().join("data"));
+        assert_eq!(
+            config_file_path().unwrap(),
+            temp_dir.path().join("config").join("burncloud").join("burncloud.toml")
+        );
+        std::env::remove_var("XDG_CONFIG_HOME");
+        std::env::remove_var("XDG_DATA_HOME");
+    }
+
+    // Misconfiguration is reported rather than silently ignored
+    std::env::set_var(CONFIG_ENV, temp_dir.path().join("missing.toml"));
+    assert!(matches!(DatabaseBuilder::from_env(), Err(DatabaseError::Configuration(_))));
+    std::env::remove_var(CONFIG_ENV);
+
+    std::env::set_var(DATABASE_URL_ENV, "sqlite://data.db?mode=ro");
+    assert!(matches!(DatabaseBuilder::from_env(), Err(DatabaseError::Configuration(_))));
+
+    clear_env();
+}
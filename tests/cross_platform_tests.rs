@@ -40,27 +40,6 @@ async fn test_cross_platform_path_generation() {
     }
 }
 
-#[cfg(not(target_os = "windows"))]
-fn validate_unix_path(path: &Path) {
-    let path_str = path.to_string_lossy();
-
-    // Should contain Unix-specific path components
-    assert!(
-        path_str.contains(".burncloud"),
-        "Unix path should contain .burncloud, got: {}",
-        path_str
-    );
-
-    // Should use forward slashes
-    assert!(path_str.contains('/'), "Unix path should contain forward slashes");
-
-    // Should start with root or home
-    assert!(
-        path_str.starts_with('/') || path_str.starts_with('~'),
-        "Unix path should be absolute"
-    );
-}
-
 #[cfg(target_os = "windows")]
 fn validate_windows_path(path: &Path) {
     let path_str = path.to_string_lossy();
@@ -92,8 +71,8 @@ fn validate_unix_path(path: &Path) {
 
     // Should contain Unix-specific path components
     assert!(
-        path_str.contains(".burncloud"),
-        "Unix path should contain .burncloud, got: {}",
+        path_str.contains("burncloud"),
+        "Unix path should contain burncloud, got: {}",
         path_str
     );
 
@@ -272,7 +251,7 @@ fn test_environment_variable_handling() {
         match path_result {
             Ok(path) => {
                 println!("✓ Unix path resolved: {}", path.display());
-                assert!(path.to_string_lossy().contains(".burncloud"));
+                assert!(path.to_string_lossy().contains("burncloud"));
             }
             Err(DatabaseError::PathResolution(msg)) => {
                 println!("Path resolution failed (acceptable): {}", msg);
@@ -364,6 +343,10 @@ fn get_test_default_path() -> Result<PathBuf> {
             .join("AppData")
             .join("Local")
             .join("BurnCloud")
+    } else if cfg!(target_os = "linux") {
+        dirs::data_dir()
+            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
+            .join("burncloud")
     } else {
         dirs::home_dir()
             .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
@@ -11,8 +11,7 @@ async fn test_database_new() {
     let db_result = Database::new().await;
     // Note: This might fail in some environments due to SQLite configuration,
     // but the path resolution and API structure are correct
-    if db_result.is_ok() {
-        let db = db_result.unwrap();
+    if let Ok(db) = db_result {
         // The database should be initialized and have a connection (test via connection method)
         assert!(db.connection().is_ok());
         let _ = db.close().await;
@@ -29,8 +28,7 @@ async fn test_create_default_database() {
     let db_result = create_default_database().await;
     // Note: This might fail in some environments due to SQLite configuration,
     // but the path resolution and API structure are correct
-    if db_result.is_ok() {
-        let db = db_result.unwrap();
+    if let Ok(db) = db_result {
         let _ = db.close().await;
     }
 }
@@ -45,9 +43,12 @@ fn test_get_default_database_path() {
     assert!(path.to_string_lossy().contains("data.db"));
 
     // On Windows, should contain AppData\Local\BurnCloud
-    // On Linux, should contain .burncloud
+    // On Linux, should follow XDG: $XDG_DATA_HOME/burncloud (~/.local/share/burncloud)
     if cfg!(target_os = "windows") {
         assert!(path.to_string_lossy().contains("AppData\\Local\\BurnCloud"));
+    } else if cfg!(target_os = "linux") {
+        let data_dir = dirs::data_dir().expect("data directory should resolve");
+        assert_eq!(path, data_dir.join("burncloud").join("data.db"));
     } else {
         assert!(path.to_string_lossy().contains(".burncloud"));
     }
@@ -63,8 +64,7 @@ fn test_is_windows() {
 async fn test_api_consistency() {
     // Test that Database::new() creates an initialized database
     let db_result = Database::new().await;
-    if db_result.is_ok() {
-        let db = db_result.unwrap();
+    if let Ok(db) = db_result {
         // Should be initialized and have a connection (test via connection method)
         assert!(db.connection().is_ok());
         // Note: We can't test database_path directly as it's private
@@ -0,0 +1,147 @@
+use burncloud_database::{from_row, Database, DatabaseBuilder, Entity};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// Tests for #[derive(Entity)], built with the `derive` feature
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Pricing {
+    input_per_million: f64,
+    output_per_million: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ready,
+    Downloading,
+}
+
+#[derive(Debug, Clone, PartialEq, Entity)]
+#[entity(table = "ai_models")]
+struct Model {
+    #[entity(primary_key, rename = "model_id")]
+    id: Option<i64>,
+    #[entity(rename = "display_name")]
+    name: String,
+    #[entity(json)]
+    status: Status,
+    #[entity(json)]
+    pricing: Option<Pricing>,
+    #[entity(json)]
+    tags: Vec<String>,
+    enabled: bool,
+    checksum: Vec<u8>,
+    #[entity(skip)]
+    download_progress: Option<f32>,
+    #[entity(created_at)]
+    created_at: Option<DateTime<Utc>>,
+    #[entity(updated_at)]
+    updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, PartialEq, Entity)]
+struct ApiKey {
+    id: String,
+    owner: String,
+}
+
+async fn database() -> Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_script(
+        "CREATE TABLE ai_models (
+            model_id INTEGER PRIMARY KEY,
+            display_name TEXT NOT NULL,
+            status TEXT NOT NULL,
+            pricing TEXT,
+            tags TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL,
+            checksum BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE TABLE api_key (id TEXT PRIMARY KEY, owner TEXT NOT NULL);",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+fn model(name: &str) -> Model {
+    Model {
+        id: None,
+        name: name.to_string(),
+        status: Status::Downloading,
+        pricing: Some(Pricing { input_per_million: 0.5, output_per_million: 1.5 }),
+        tags: vec!["chat".to_string()],
+        enabled: true,
+        checksum: vec![0xca, 0xfe],
+        download_progress: Some(0.5),
+        created_at: None,
+        updated_at: None,
+    }
+}
+
+#[test]
+fn test_derived_mapping() {
+    assert_eq!(Model::TABLE, "ai_models");
+    assert_eq!(Model::PRIMARY_KEY, "model_id");
+    assert_eq!(
+        Model::COLUMNS,
+        ["model_id", "display_name", "status", "pricing", "tags", "enabled", "checksum", "created_at", "updated_at"]
+    );
+    assert_eq!(Model::CREATED_AT, Some("created_at"));
+    assert_eq!(Model::UPDATED_AT, Some("updated_at"));
+
+    assert_eq!(ApiKey::TABLE, "api_key");
+    assert_eq!(ApiKey::PRIMARY_KEY, "id");
+    assert_eq!(ApiKey::CREATED_AT, None);
+}
+
+#[tokio::test]
+async fn test_derived_entity_round_trips_through_repository() {
+    let db = database().await;
+    let models = db.repository::<Model>();
+
+    let stored = models.insert(&model("llama-3")).await.unwrap();
+    assert_eq!(stored.id, Some(1));
+    assert_eq!(stored.status, Status::Downloading);
+    assert_eq!(stored.pricing, model("llama-3").pricing);
+    assert_eq!(stored.checksum, vec![0xca, 0xfe]);
+    // Skipped fields are not stored
+    assert_eq!(stored.download_progress, None);
+    let created_at = stored.created_at.expect("created_at is set on insert");
+    assert_eq!(stored.updated_at, Some(created_at));
+
+    let raw: (String, String, String) = db
+        .fetch_one("SELECT status, pricing, tags FROM ai_models WHERE model_id = 1")
+        .await
+        .unwrap();
+    assert_eq!(raw.0, "\"downloading\"");
+    assert_eq!(raw.2, "[\"chat\"]");
+
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let mut updated = stored.clone();
+    updated.status = Status::Ready;
+    updated.pricing = None;
+    updated.created_at = None;
+    assert!(models.update(&updated).await.unwrap());
+
+    let loaded = models.get(1).await.unwrap().unwrap();
+    assert_eq!(loaded.status, Status::Ready);
+    assert_eq!(loaded.pricing, None);
+    assert_eq!(loaded.created_at, Some(created_at), "created_at is never updated");
+    assert!(loaded.updated_at.unwrap() > created_at);
+
+    // FromRow is derived too, so the typed fetch methods work directly
+    let all: Vec<Model> = db.fetch_all("SELECT * FROM ai_models").await.unwrap();
+    assert_eq!(all, vec![loaded]);
+
+    let keys = db.repository::<ApiKey>();
+    let key = ApiKey { id: "sk-1".to_string(), owner: "ops".to_string() };
+    assert_eq!(keys.insert(&key).await.unwrap(), key);
+    assert!(keys.exists("sk-1".to_string()).await.unwrap());
+    let rows = db.query("SELECT * FROM api_key").await.unwrap();
+    let (id, owner): (String, String) = from_row(&rows[0]).unwrap();
+    assert_eq!((id.as_str(), owner.as_str()), ("sk-1", "ops"));
+}
@@ -0,0 +1,148 @@
+use burncloud_database::{from_row, params, DatabaseBuilder, DatabaseError, ErrorKind};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Tests for deserializing rows with serde instead of sqlx::FromRow
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ready,
+    Downloading,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Pricing {
+    input_per_million: f64,
+    output_per_million: f64,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Model {
+    id: i64,
+    uid: Uuid,
+    #[serde(rename = "display_name")]
+    name: String,
+    status: Status,
+    enabled: bool,
+    context_length: Option<u32>,
+    pricing: Pricing,
+    tags: Vec<String>,
+    metadata: Option<HashMap<String, String>>,
+    checksum: Vec<u8>,
+    created_at: DateTime<Utc>,
+}
+
+#[tokio::test]
+async fn test_fetch_as_maps_columns_to_fields() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE models (
+            id INTEGER PRIMARY KEY,
+            uid TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            status TEXT NOT NULL,
+            enabled INTEGER NOT NULL,
+            context_length INTEGER,
+            pricing TEXT NOT NULL,
+            tags TEXT NOT NULL,
+            metadata TEXT,
+            checksum BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            unused TEXT
+        )",
+    )
+    .await
+    .unwrap();
+
+    let uid = Uuid::new_v4();
+    let created_at = Utc.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap();
+    db.execute_query_with_params(
+        "INSERT INTO models (uid, display_name, status, enabled, context_length, pricing, tags, metadata, checksum, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?), (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            uid, "Llama 3", "ready", true, 8192,
+            serde_json::json!({ "input_per_million": 0.2, "output_per_million": 0.6 }),
+            serde_json::json!(["chat", "open"]), serde_json::json!({ "family": "llama" }), vec![1_u8, 2, 3], created_at,
+            Uuid::nil(), "Pending", "downloading", false, None::<i64>,
+            serde_json::json!({ "input_per_million": 1, "output_per_million": 2 }),
+            serde_json::json!([]), None::<String>, Vec::<u8>::new(), created_at
+        ],
+    )
+    .await
+    .unwrap();
+
+    let models: Vec<Model> = db.fetch_all_as("SELECT * FROM models ORDER BY id", params![]).await.unwrap();
+    assert_eq!(
+        models[0],
+        Model {
+            id: 1,
+            uid,
+            name: "Llama 3".to_string(),
+            status: Status::Ready,
+            enabled: true,
+            context_length: Some(8192),
+            pricing: Pricing { input_per_million: 0.2, output_per_million: 0.6 },
+            tags: vec!["chat".to_string(), "open".to_string()],
+            metadata: Some(HashMap::from([("family".to_string(), "llama".to_string())])),
+            checksum: vec![1, 2, 3],
+            created_at,
+        }
+    );
+    assert_eq!(models[1].status, Status::Downloading);
+    assert!(!models[1].enabled);
+    assert_eq!(models[1].context_length, None);
+    assert_eq!(models[1].pricing.output_per_million, 2.0);
+    assert!(models[1].tags.is_empty() && models[1].metadata.is_none());
+
+    let one: Model = db.fetch_one_as("SELECT * FROM models WHERE id = ?", params![2]).await.unwrap();
+    assert_eq!(one.uid, Uuid::nil());
+
+    let none: Option<Model> = db.fetch_optional_as("SELECT * FROM models WHERE id = ?", params![3]).await.unwrap();
+    assert!(none.is_none());
+
+    let err = db.fetch_one_as::<Model, _>("SELECT * FROM models WHERE id = ?", params![3]).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn test_tuples_and_serialization_errors() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+
+    // Tuples are filled by position
+    let (id, name, score): (i64, String, f64) = db
+        .fetch_one_as("SELECT 7 AS id, 'seven' AS name, 7 AS score", params![])
+        .await
+        .unwrap();
+    assert_eq!((id, name.as_str(), score), (7, "seven", 7.0));
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Strict {
+        id: i64,
+        pricing: Pricing,
+    }
+
+    let err = db
+        .fetch_one_as::<Strict, _>("SELECT 1 AS id, 'not json' AS pricing", params![])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DatabaseError::Serialization(_)), "{:?}", err);
+
+    let err = db.fetch_one_as::<Strict, _>("SELECT 1 AS id", params![]).await.unwrap_err();
+    assert!(err.to_string().contains("missing field `pricing`"), "{}", err);
+
+    // Rows from query() can be converted too, inside or outside a transaction
+    let mut tx = db.begin().await.unwrap();
+    let rows = tx.query("SELECT 'x' AS name").await.unwrap();
+    #[derive(Deserialize)]
+    struct Named {
+        name: String,
+    }
+    assert_eq!(from_row::<Named>(&rows[0]).unwrap().name, "x");
+    let all: Vec<(String,)> = tx.fetch_all_as("SELECT 'a' UNION ALL SELECT 'b'", params![]).await.unwrap();
+    assert_eq!(all.len(), 2);
+    tx.rollback().await.unwrap();
+}
@@ -100,7 +100,7 @@ async fn test_connection_pool_exhaustion() {
                 let result = sqlx::query(&format!("SELECT {} as operation_id", i))
                     .execute(connection.pool())
                     .await
-                    .map_err(|e| burncloud_database::DatabaseError::Connection(e));
+                    .map_err(burncloud_database::DatabaseError::Connection);
                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                 result
             });
@@ -251,7 +251,7 @@ fn test_error_message_quality() {
     assert!(error_msg.len() > 20);
 
     // Test that errors implement standard traits
-    assert!(format!("{:?}", path_error).len() > 0); // Debug formatting
+    assert!(!format!("{:?}", path_error).is_empty()); // Debug formatting
 
     println!("✓ Error messages are informative and well-formatted");
 }
@@ -294,6 +294,7 @@ async fn test_resource_cleanup_on_errors() {
 }
 
 // Helper function for tests
+#[allow(dead_code)]
 fn get_test_default_path() -> Result<PathBuf> {
     use burncloud_database::DatabaseError;
 
@@ -304,6 +305,10 @@ fn get_test_default_path() -> Result<PathBuf> {
             .join("AppData")
             .join("Local")
             .join("BurnCloud")
+    } else if cfg!(target_os = "linux") {
+        dirs::data_dir()
+            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
+            .join("burncloud")
     } else {
         dirs::home_dir()
             .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
@@ -0,0 +1,154 @@
+use burncloud_database::{
+    params, ConstraintViolation, Database, DatabaseBuilder, DatabaseError, ErrorKind, PragmaProfile, QueryContext,
+    TransactionBehavior,
+};
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Tests for classifying SQLite failures with DatabaseError::kind()
+
+async fn memory_database() -> Database {
+    let db = DatabaseBuilder::new().in_memory().max_connections(1).build().await.unwrap();
+    sqlx::raw_sql(
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);
+         CREATE TABLE api_tokens (
+             id INTEGER PRIMARY KEY,
+             user_id INTEGER NOT NULL REFERENCES users(id),
+             name TEXT NOT NULL,
+             quota INTEGER CONSTRAINT positive_quota CHECK (quota > 0),
+             UNIQUE (user_id, name)
+         );
+         CREATE UNIQUE INDEX idx_users_email ON users (lower(email));
+         INSERT INTO users (id, email) VALUES (1, 'a@example.com');
+         INSERT INTO api_tokens (user_id, name, quota) VALUES (1, 'ci', 10);",
+    )
+    .execute(db.connection().unwrap().pool())
+    .await
+    .unwrap();
+    db
+}
+
+#[tokio::test]
+async fn test_constraint_violations() {
+    let db = memory_database().await;
+
+    let err = db
+        .execute_query_with_params("INSERT INTO api_tokens (user_id, name, quota) VALUES (?, ?, ?)", params![1, "ci", 5])
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+    assert_eq!(err.sqlite_code(), Some(2067));
+    assert_eq!(
+        err.constraint_violation(),
+        Some(ConstraintViolation {
+            table: Some("api_tokens".to_string()),
+            columns: vec!["user_id".to_string(), "name".to_string()],
+            constraint: None,
+        })
+    );
+
+    let err = db.execute_query("INSERT INTO users (id, email) VALUES (1, 'b@example.com')").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+    assert_eq!(err.constraint_violation().unwrap().columns, vec!["id".to_string()]);
+
+    let err = db.execute_query("INSERT INTO users (email) VALUES ('A@example.com')").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+    assert_eq!(err.constraint_violation().unwrap().constraint.as_deref(), Some("idx_users_email"));
+
+    let err = db.execute_query("INSERT INTO api_tokens (user_id, name) VALUES (42, 'x')").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ForeignKeyViolation);
+
+    let err = db.execute_query("INSERT INTO api_tokens (user_id) VALUES (1)").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotNullViolation);
+    let violation = err.constraint_violation().unwrap();
+    assert_eq!(violation.table.as_deref(), Some("api_tokens"));
+    assert_eq!(violation.columns, vec!["name".to_string()]);
+
+    let err = db.execute_query("INSERT INTO api_tokens (user_id, name, quota) VALUES (1, 'y', 0)").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::CheckViolation);
+    assert_eq!(err.constraint_violation().unwrap().constraint.as_deref(), Some("positive_quota"));
+
+    // Constraint errors are still sqlx errors underneath, and are never retryable
+    assert!(matches!(err, DatabaseError::Query { source: sqlx::Error::Database(_), .. }));
+    assert!(!err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_query_error_kinds() {
+    let db = memory_database().await;
+
+    let err = db.fetch_one::<(i64,)>("SELECT id FROM users WHERE id = 99").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(err.constraint_violation(), None);
+
+    let err = db.execute_query("SELEC 1").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Syntax);
+
+    let err = db.execute_query("SELECT missing FROM users").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+
+    db.execute_query("PRAGMA query_only = ON").await.unwrap();
+    let err = db.execute_query("DELETE FROM api_tokens").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ReadOnly);
+
+    assert_eq!(DatabaseError::NotInitialized.kind(), ErrorKind::Other);
+    assert_eq!(DatabaseError::NotInitialized.sqlite_code(), None);
+}
+
+#[tokio::test]
+async fn test_busy_is_retryable() {
+    let dir = TempDir::new().unwrap();
+    let pragmas = PragmaProfile {
+        busy_timeout: Duration::ZERO,
+        ..PragmaProfile::default()
+    };
+    let db = DatabaseBuilder::new().path(dir.path().join("busy.db")).pragmas(pragmas).build().await.unwrap();
+    db.execute_query("CREATE TABLE jobs (id INTEGER PRIMARY KEY)").await.unwrap();
+
+    let mut holder = db.begin_with(TransactionBehavior::Immediate).await.unwrap();
+    holder.execute_query("INSERT INTO jobs DEFAULT VALUES").await.unwrap();
+
+    let err = db.execute_query("INSERT INTO jobs DEFAULT VALUES").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Busy);
+    assert!(err.is_retryable());
+    holder.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_query_errors_carry_sql_and_redacted_params() {
+    let db = memory_database().await;
+
+    let err = db
+        .execute_query_with_params(
+            "INSERT INTO api_tokens (user_id, name, quota)\n     VALUES (?, ?, ?)",
+            params![1, "ci", None::<i64>],
+        )
+        .await
+        .unwrap_err();
+
+    let context = err.query_context().expect("query failures carry their SQL");
+    assert_eq!(context.sql(), "INSERT INTO api_tokens (user_id, name, quota) VALUES (?, ?, ?)");
+    assert_eq!(context.params(), ["integer", "text(2 chars)", "null"]);
+
+    let message = err.to_string();
+    assert!(message.contains("UNIQUE constraint failed"), "{}", message);
+    assert!(message.contains("sql: INSERT INTO api_tokens"), "{}", message);
+    assert!(message.contains("params: [integer, text(2 chars), null]"), "{}", message);
+    assert!(!message.contains("'ci'"), "values must not be logged: {}", message);
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+
+    // Long statements are truncated
+    let long_sql = format!("SELECT {} FROM missing_table", vec!["1"; 200].join(", "));
+    let err = db.execute_query(&long_sql).await.unwrap_err();
+    let sql = err.query_context().unwrap().sql();
+    assert!(sql.ends_with("..."));
+    assert_eq!(sql.chars().count(), QueryContext::MAX_SQL_LEN + 3);
+
+    // Errors inside transactions are wrapped the same way, while errors from elsewhere are not
+    let mut tx = db.begin().await.unwrap();
+    let err = tx.fetch_one::<(i64,)>("SELECT id FROM users WHERE id = 99").await.unwrap_err();
+    assert_eq!(err.query_context().unwrap().sql(), "SELECT id FROM users WHERE id = 99");
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    tx.rollback().await.unwrap();
+    assert!(DatabaseError::NotInitialized.query_context().is_none());
+}
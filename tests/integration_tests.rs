@@ -1,7 +1,6 @@
 use burncloud_database::{Database, DatabaseError, Result, create_default_database};
 use std::fs;
 use std::path::PathBuf;
-use tempfile::TempDir;
 
 /// Integration tests for the default database location feature
 /// These tests focus on functional validation and real-world scenarios
@@ -32,6 +31,7 @@ async fn test_create_default_database_end_to_end() {
 
             // Verify data can be retrieved
             #[derive(sqlx::FromRow)]
+            #[allow(dead_code)]
             struct TestRow {
                 id: i64,
                 name: String,
@@ -122,10 +122,10 @@ async fn test_platform_specific_paths() {
                     path_str
                 );
             } else {
-                // Linux/Unix should use ~/.burncloud
+                // Linux should use $XDG_DATA_HOME/burncloud, other Unix ~/.burncloud
                 assert!(
-                    path_str.contains(".burncloud"),
-                    "Linux path should contain .burncloud, got: {}",
+                    path_str.contains("burncloud"),
+                    "Linux path should contain burncloud, got: {}",
                     path_str
                 );
             }
@@ -219,6 +219,7 @@ async fn test_database_persistence() {
                 let db2_result = Database::new().await;
                 if let Ok(db2) = db2_result {
                     #[derive(sqlx::FromRow)]
+                    #[allow(dead_code)]
                     struct PersistenceRow {
                         value: String,
                     }
@@ -350,6 +351,10 @@ fn get_test_default_path() -> Result<PathBuf> {
             .join("AppData")
             .join("Local")
             .join("BurnCloud")
+    } else if cfg!(target_os = "linux") {
+        dirs::data_dir()
+            .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
+            .join("burncloud")
     } else {
         dirs::home_dir()
             .ok_or_else(|| DatabaseError::PathResolution("Home directory not found".to_string()))?
@@ -0,0 +1,96 @@
+use burncloud_database::{params, row_to_json, DatabaseBuilder, JsonRow};
+use serde_json::json;
+
+// Tests for converting rows into JSON objects
+
+#[tokio::test]
+async fn test_query_json_respects_storage_classes() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE models (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            score REAL,
+            weights BLOB,
+            enabled BOOLEAN NOT NULL,
+            size NUMERIC,
+            note TEXT
+        )",
+    )
+    .await
+    .unwrap();
+    db.execute_query_with_params(
+        "INSERT INTO models (name, score, weights, enabled, size, note) VALUES (?, ?, ?, ?, ?, ?), (?, ?, ?, ?, ?, ?)",
+        params![
+            "llama", 0.75, vec![0_u8, 1, 2, 255], true, "70", None::<String>,
+            "gpt", None::<f64>, None::<Vec<u8>>, false, "1.5", "12"
+        ],
+    )
+    .await
+    .unwrap();
+
+    let rows = db
+        .query_json("SELECT id, name, score, weights, enabled, size, note FROM models ORDER BY id", params![])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows,
+        vec![
+            json!({
+                "id": 1,
+                "name": "llama",
+                "score": 0.75,
+                "weights": "AAEC/w==",
+                "enabled": true,
+                "size": 70,
+                "note": null
+            }),
+            // NUMERIC affinity stores "1.5" as REAL; TEXT affinity keeps "12" as a string
+            json!({
+                "id": 2,
+                "name": "gpt",
+                "score": null,
+                "weights": null,
+                "enabled": false,
+                "size": 1.5,
+                "note": "12"
+            }),
+        ]
+    );
+
+    // JsonRow keeps the column order of the query, also when serialized
+    let rows = db
+        .query("SELECT id, name, score, weights, enabled, size, note FROM models ORDER BY id")
+        .await
+        .unwrap();
+    let ordered = JsonRow::from_row(&rows[1]).unwrap();
+    let keys: Vec<&str> = ordered.columns().collect();
+    assert_eq!(keys, ["id", "name", "score", "weights", "enabled", "size", "note"]);
+    assert_eq!(
+        serde_json::to_string(&ordered).unwrap(),
+        r#"{"id":2,"name":"gpt","score":null,"weights":null,"enabled":false,"size":1.5,"note":"12"}"#
+    );
+    assert_eq!(ordered, row_to_json(&rows[1]).unwrap());
+    assert_eq!(ordered["name"], "gpt");
+    assert!(ordered["missing"].is_null());
+}
+
+#[tokio::test]
+async fn test_row_to_json_for_expressions_and_params() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+
+    let rows = db
+        .query_with_params("SELECT ? + 1 AS next, upper(?) AS shout, x'CAFE' AS raw, 1.0 / 0 AS undefined", params![41, "hi"])
+        .await
+        .unwrap();
+    assert_eq!(
+        row_to_json(&rows[0]).unwrap(),
+        json!({ "next": 42, "shout": "HI", "raw": "yv4=", "undefined": null })
+    );
+
+    let mut tx = db.begin().await.unwrap();
+    let rows = tx.query_json("SELECT 'a' AS letter UNION ALL SELECT 'b'", params![]).await.unwrap();
+    assert_eq!(rows, vec![json!({ "letter": "a" }), json!({ "letter": "b" })]);
+    tx.rollback().await.unwrap();
+}
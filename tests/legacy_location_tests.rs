@@ -0,0 +1,132 @@
+use burncloud_database::{relocate_legacy_database, DatabaseBuilder};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+// Tests for copying a database out of a legacy location (~/.burncloud on Linux)
+// These use temporary directories so the real home directory is never touched
+
+async fn create_legacy_database(path: &Path) {
+    let db = DatabaseBuilder::new().path(path).build().await.unwrap();
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)").await.unwrap();
+    db.execute_query("INSERT INTO models (name) VALUES ('legacy-model')").await.unwrap();
+    db.close().await.unwrap();
+}
+
+async fn model_names(path: &Path) -> Vec<String> {
+    let db = DatabaseBuilder::new().path(path).create_if_missing(false).build().await.unwrap();
+    let rows: Vec<(String,)> = db.fetch_all("SELECT name FROM models").await.unwrap();
+    db.close().await.unwrap();
+    rows.into_iter().map(|(name,)| name).collect()
+}
+
+#[tokio::test]
+async fn test_legacy_database_is_copied_with_wal_contents() {
+    let temp_dir = TempDir::new().unwrap();
+    let legacy_dir = temp_dir.path().join(".burncloud");
+    let legacy = legacy_dir.join("data.db");
+    let target = temp_dir.path().join("share").join("burncloud").join("data.db");
+
+    // Snapshot an open WAL database, as if the application had crashed: the committed rows
+    // only exist in the -wal sidecar
+    let scratch = temp_dir.path().join("scratch.db");
+    let db = DatabaseBuilder::new().path(&scratch).build().await.unwrap();
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)").await.unwrap();
+    db.execute_query("INSERT INTO models (name) VALUES ('legacy-model')").await.unwrap();
+    fs::create_dir_all(&legacy_dir).unwrap();
+    for suffix in ["", "-wal", "-shm"] {
+        let source = temp_dir.path().join(format!("scratch.db{}", suffix));
+        assert!(source.exists(), "{} should exist while the database is open", source.display());
+        fs::copy(&source, legacy_dir.join(format!("data.db{}", suffix))).unwrap();
+    }
+    db.close().await.unwrap();
+
+    let resolved = relocate_legacy_database(&legacy, &target).await.unwrap();
+    assert_eq!(resolved, target);
+    assert!(target.exists(), "Database should be at the new location");
+    assert!(!temp_dir.path().join("share").join("burncloud").join("data.db-partial").exists());
+    assert!(legacy.exists(), "Legacy database should be left in place");
+
+    assert_eq!(model_names(&target).await, vec!["legacy-model".to_string()]);
+    assert_eq!(model_names(&legacy).await, vec!["legacy-model".to_string()]);
+}
+
+#[tokio::test]
+async fn test_existing_target_wins_over_legacy() {
+    let temp_dir = TempDir::new().unwrap();
+    let legacy = temp_dir.path().join("legacy").join("data.db");
+    let target = temp_dir.path().join("current").join("data.db");
+    create_legacy_database(&legacy).await;
+    fs::create_dir_all(target.parent().unwrap()).unwrap();
+    fs::write(temp_dir.path().join("legacy").join("burncloud.toml"), "").unwrap();
+
+    let db = DatabaseBuilder::new().path(&target).build().await.unwrap();
+    db.close().await.unwrap();
+
+    let resolved = relocate_legacy_database(&legacy, &target).await.unwrap();
+    assert_eq!(resolved, target);
+    assert!(legacy.exists(), "Legacy database must not be touched when the target exists");
+}
+
+#[tokio::test]
+async fn test_no_legacy_database() {
+    let temp_dir = TempDir::new().unwrap();
+    let legacy = temp_dir.path().join("legacy").join("data.db");
+    let target = temp_dir.path().join("current").join("data.db");
+
+    let resolved = relocate_legacy_database(&legacy, &target).await.unwrap();
+    assert_eq!(resolved, target);
+    assert!(!target.exists(), "Nothing should be created when there is nothing to copy");
+}
+
+#[tokio::test]
+async fn test_legacy_directory_with_other_files_is_kept() {
+    let temp_dir = TempDir::new().unwrap();
+    let legacy_dir = temp_dir.path().join(".burncloud");
+    let legacy = legacy_dir.join("data.db");
+    let target = temp_dir.path().join("share").join("data.db");
+    create_legacy_database(&legacy).await;
+    fs::write(legacy_dir.join("burncloud.toml"), "[database]\n").unwrap();
+
+    relocate_legacy_database(&legacy, &target).await.unwrap();
+    assert!(target.exists());
+    assert!(legacy_dir.join("burncloud.toml").exists(), "Unrelated files must stay in place");
+}
+
+#[tokio::test]
+async fn test_legacy_database_stays_usable_by_open_connections() {
+    use burncloud_database::sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+    use burncloud_database::sqlx::{ConnectOptions, Connection};
+
+    let temp_dir = TempDir::new().unwrap();
+    let legacy = temp_dir.path().join(".burncloud").join("data.db");
+    let target = temp_dir.path().join("share").join("burncloud").join("data.db");
+
+    // Old builds used sqlx defaults, i.e. a rollback journal rather than WAL. One connection
+    // sits idle, as an old build that is still running would; another is mid-write
+    fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+    let options = SqliteConnectOptions::new()
+        .filename(&legacy)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Delete);
+    let mut idle = options.connect().await.unwrap();
+    sqlx::query("CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)").execute(&mut idle).await.unwrap();
+    sqlx::query("INSERT INTO models (name) VALUES ('committed')").execute(&mut idle).await.unwrap();
+    let mut writer = options.connect().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE").execute(&mut writer).await.unwrap();
+    sqlx::query("INSERT INTO models (name) VALUES ('uncommitted')").execute(&mut writer).await.unwrap();
+
+    let resolved = relocate_legacy_database(&legacy, &target).await.unwrap();
+    assert_eq!(resolved, target);
+    assert_eq!(model_names(&target).await, vec!["committed".to_string()]);
+
+    // Both connections keep working against the untouched legacy file
+    sqlx::query("COMMIT").execute(&mut writer).await.unwrap();
+    sqlx::query("INSERT INTO models (name) VALUES ('after-copy')").execute(&mut idle).await.unwrap();
+    writer.close().await.unwrap();
+    idle.close().await.unwrap();
+    assert_eq!(
+        model_names(&legacy).await,
+        vec!["committed".to_string(), "uncommitted".to_string(), "after-copy".to_string()]
+    );
+}
@@ -0,0 +1,310 @@
+use burncloud_database::{DatabaseConnection, DatabaseError, Migration, Migrator};
+
+// Tests for the versioned migration runner
+// Each test uses its own in-memory database so bookkeeping never leaks between tests
+
+async fn memory_connection() -> DatabaseConnection {
+    DatabaseConnection::new("sqlite::memory:")
+        .await
+        .expect("in-memory database should open")
+}
+
+fn base_migrations() -> Migrator {
+    let mut migrator = Migrator::new();
+    migrator
+        .add(Migration::sql(
+            1,
+            "create_models",
+            "CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+        ))
+        .unwrap();
+    migrator
+        .add(Migration::sql(
+            2,
+            "add_model_status",
+            "ALTER TABLE models ADD COLUMN status TEXT NOT NULL DEFAULT 'pending';
+             CREATE INDEX idx_models_status ON models (status);",
+        ))
+        .unwrap();
+    migrator
+}
+
+#[tokio::test]
+async fn test_migrate_applies_pending_in_order() {
+    let conn = memory_connection().await;
+    let migrator = base_migrations();
+
+    let applied = migrator.run(&conn).await.expect("migrations should apply");
+    assert_eq!(applied, vec![1, 2]);
+
+    sqlx::query("INSERT INTO models (name) VALUES ('llama')")
+        .execute(conn.pool())
+        .await
+        .expect("migrated schema should accept inserts");
+    let status: String = sqlx::query_scalar("SELECT status FROM models")
+        .fetch_one(conn.pool())
+        .await
+        .unwrap();
+    assert_eq!(status, "pending");
+
+    let history = migrator.applied(&conn).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].name, "create_models");
+    assert_eq!(history[1].version, 2);
+}
+
+#[tokio::test]
+async fn test_migrate_is_idempotent() {
+    let conn = memory_connection().await;
+    let mut migrator = base_migrations();
+
+    assert_eq!(migrator.run(&conn).await.unwrap(), vec![1, 2]);
+    assert!(migrator.run(&conn).await.unwrap().is_empty(), "Nothing should be pending");
+
+    migrator
+        .add(Migration::sql(3, "create_tags", "CREATE TABLE tags (name TEXT PRIMARY KEY)"))
+        .unwrap();
+    assert_eq!(migrator.run(&conn).await.unwrap(), vec![3]);
+}
+
+#[tokio::test]
+async fn test_code_migration() {
+    let conn = memory_connection().await;
+    let mut migrator = base_migrations();
+    migrator
+        .add(Migration::code(3, "seed_models", |conn| {
+            Box::pin(async move {
+                for name in ["gpt", "qwen"] {
+                    sqlx::query("INSERT INTO models (name) VALUES (?)")
+                        .bind(name)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+                Ok(())
+            })
+        }))
+        .unwrap();
+
+    migrator.run(&conn).await.unwrap();
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM models")
+        .fetch_one(conn.pool())
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn test_failed_migration_rolls_back_batch() {
+    let conn = memory_connection().await;
+    let mut migrator = base_migrations();
+    migrator
+        .add(Migration::sql(3, "broken", "CREATE TABLE broken (id INTEGER PRIMARY KEY); NOT VALID SQL"))
+        .unwrap();
+
+    let err = migrator.run(&conn).await.expect_err("broken migration should fail");
+    match err {
+        DatabaseError::Migration(message) => assert!(message.contains("broken"), "{}", message),
+        other => panic!("Unexpected error type: {}", other),
+    }
+
+    // Nothing from the batch should have been committed
+    assert!(migrator.applied(&conn).await.unwrap().is_empty());
+    let tables: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE name IN ('models', 'broken')")
+        .fetch_one(conn.pool())
+        .await
+        .unwrap();
+    assert_eq!(tables, 0);
+}
+
+#[tokio::test]
+async fn test_mismatch_with_recorded_history() {
+    let conn = memory_connection().await;
+    base_migrations().run(&conn).await.unwrap();
+
+    // A build that no longer knows about migration 2
+    let mut older = Migrator::new();
+    older
+        .add(Migration::sql(1, "create_models", "CREATE TABLE models (id INTEGER PRIMARY KEY)"))
+        .unwrap();
+    assert!(matches!(older.run(&conn).await, Err(DatabaseError::Migration(_))));
+
+    // A build that renamed migration 1
+    let mut renamed = Migrator::new();
+    renamed.add(Migration::sql(1, "create_model_table", "SELECT 1")).unwrap();
+    renamed.add(Migration::sql(2, "add_model_status", "SELECT 1")).unwrap();
+    assert!(matches!(renamed.run(&conn).await, Err(DatabaseError::Migration(_))));
+
+    // A build that inserts a migration below the current version
+    let mut gap = base_migrations();
+    gap.add(Migration::sql(4, "four", "SELECT 1")).unwrap();
+    gap.run(&conn).await.unwrap();
+    let mut late = base_migrations();
+    late.add(Migration::sql(3, "three", "SELECT 1")).unwrap();
+    late.add(Migration::sql(4, "four", "SELECT 1")).unwrap();
+    assert!(matches!(late.run(&conn).await, Err(DatabaseError::Migration(_))));
+}
+
+#[test]
+fn test_duplicate_and_invalid_versions_rejected() {
+    let mut migrator = base_migrations();
+    assert!(matches!(
+        migrator.add(Migration::sql(2, "again", "SELECT 1")),
+        Err(DatabaseError::Migration(_))
+    ));
+    assert!(matches!(
+        migrator.add(Migration::sql(0, "zero", "SELECT 1")),
+        Err(DatabaseError::Migration(_))
+    ));
+    assert_eq!(migrator.latest_version(), Some(2));
+}
+
+#[tokio::test]
+async fn test_checksums_recorded_for_sql_migrations() {
+    let conn = memory_connection().await;
+    let mut migrator = base_migrations();
+    migrator
+        .add(Migration::code(3, "noop", |_| Box::pin(async { Ok(()) })))
+        .unwrap();
+    migrator.run(&conn).await.unwrap();
+
+    let history = migrator.applied(&conn).await.unwrap();
+    assert_eq!(history[0].checksum, migrator.migrations()[0].checksum());
+    assert_eq!(history[0].checksum.as_ref().map(String::len), Some(64));
+    assert_eq!(history[2].checksum, None, "Code migrations have no checksum");
+}
+
+#[tokio::test]
+async fn test_edited_migration_is_detected() {
+    let conn = memory_connection().await;
+    base_migrations().run(&conn).await.unwrap();
+
+    let mut edited = Migrator::new();
+    edited
+        .add(Migration::sql(
+            1,
+            "create_models",
+            "CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL, size INTEGER)",
+        ))
+        .unwrap();
+    edited
+        .add(base_migrations().migrations()[1].clone())
+        .unwrap();
+
+    for result in [edited.verify(&conn).await, edited.run(&conn).await.map(|_| ())] {
+        match result {
+            Err(DatabaseError::Migration(message)) => {
+                assert!(message.contains("create_models"), "{}", message);
+                assert!(message.contains("modified"), "{}", message);
+            }
+            other => panic!("Expected checksum mismatch, got {:?}", other),
+        }
+    }
+
+    assert!(base_migrations().verify(&conn).await.is_ok());
+}
+
+fn reversible_migrations() -> Migrator {
+    let mut migrator = Migrator::new();
+    migrator
+        .add(
+            Migration::sql(1, "create_models", "CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL)")
+                .with_down_sql("DROP TABLE models"),
+        )
+        .unwrap();
+    migrator
+        .add(
+            Migration::sql(2, "create_tags", "CREATE TABLE tags (name TEXT PRIMARY KEY)")
+                .with_down_sql("DROP TABLE tags"),
+        )
+        .unwrap();
+    migrator
+        .add(
+            Migration::code(3, "seed_tags", |conn| {
+                Box::pin(async move {
+                    sqlx::query("INSERT INTO tags (name) VALUES ('chat')").execute(&mut *conn).await?;
+                    Ok(())
+                })
+            })
+            .with_down_code(|conn| {
+                Box::pin(async move {
+                    sqlx::query("DELETE FROM tags WHERE name = 'chat'").execute(&mut *conn).await?;
+                    Ok(())
+                })
+            }),
+        )
+        .unwrap();
+    migrator
+}
+
+async fn table_exists(conn: &DatabaseConnection, name: &str) -> bool {
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(name)
+        .fetch_one(conn.pool())
+        .await
+        .unwrap()
+        > 0
+}
+
+#[tokio::test]
+async fn test_migrate_to_forward_and_backward() {
+    let conn = memory_connection().await;
+    let migrator = reversible_migrations();
+
+    assert_eq!(migrator.run_to(&conn, 2).await.unwrap(), vec![1, 2]);
+    assert!(table_exists(&conn, "tags").await);
+    let tag_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tags").fetch_one(conn.pool()).await.unwrap();
+    assert_eq!(tag_count, 0, "Migration 3 should not have run yet");
+
+    assert_eq!(migrator.run_to(&conn, 3).await.unwrap(), vec![3]);
+    assert_eq!(migrator.run_to(&conn, 3).await.unwrap(), Vec::<i64>::new());
+
+    assert_eq!(migrator.run_to(&conn, 1).await.unwrap(), vec![3, 2]);
+    assert!(!table_exists(&conn, "tags").await);
+    assert!(table_exists(&conn, "models").await);
+    let history = migrator.applied(&conn).await.unwrap();
+    assert_eq!(history.iter().map(|m| m.version).collect::<Vec<_>>(), vec![1]);
+
+    assert_eq!(migrator.run_to(&conn, 0).await.unwrap(), vec![1]);
+    assert!(!table_exists(&conn, "models").await);
+    assert!(migrator.applied(&conn).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_migrate_to_rejects_irreversible_and_unknown() {
+    let conn = memory_connection().await;
+    let mut migrator = reversible_migrations();
+    migrator
+        .add(Migration::sql(4, "add_tag_color", "ALTER TABLE tags ADD COLUMN color TEXT"))
+        .unwrap();
+    migrator.run(&conn).await.unwrap();
+
+    match migrator.run_to(&conn, 2).await {
+        Err(DatabaseError::Migration(message)) => assert!(message.contains("add_tag_color"), "{}", message),
+        other => panic!("Expected irreversible migration error, got {:?}", other),
+    }
+    // The failed downgrade must leave the schema untouched
+    assert_eq!(migrator.applied(&conn).await.unwrap().len(), 4);
+
+    assert!(matches!(migrator.run_to(&conn, 42).await, Err(DatabaseError::Migration(_))));
+}
+
+#[tokio::test]
+async fn test_concurrent_migrators_on_one_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("shared.db");
+
+    // Separate pools stand in for separate processes; IMMEDIATE makes the losers wait
+    let mut databases = Vec::new();
+    for _ in 0..4 {
+        let mut db = burncloud_database::Database::builder().path(&path).build().await.unwrap();
+        db.add_migrations(base_migrations().migrations().to_vec()).unwrap();
+        databases.push(db);
+    }
+    let results = futures::future::join_all(databases.iter().map(|db| db.migrate())).await;
+    let mut applied = Vec::new();
+    for result in results {
+        applied.extend(result.expect("concurrent migrate should not fail"));
+    }
+    assert_eq!(applied, vec![1, 2]);
+}
@@ -0,0 +1,130 @@
+use burncloud_database::{named_params, Database, DatabaseBuilder, DatabaseError, NamedParams, Value};
+use serde::Serialize;
+use std::collections::HashMap;
+
+// Tests for :name / @name / $name parameters resolved to positional binds
+
+async fn memory_database() -> Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE models (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            provider TEXT NOT NULL,
+            context_length INTEGER,
+            tags TEXT
+        )",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+#[derive(Serialize)]
+struct NewModel<'a> {
+    name: &'a str,
+    provider: &'a str,
+    context_length: Option<i64>,
+    tags: Vec<&'a str>,
+    unused: bool,
+}
+
+#[test]
+fn test_resolve_rewrites_placeholders() {
+    let params = named_params! { "id" => 1, "name" => "gpt" };
+
+    let (sql, values) = params
+        .resolve("SELECT * FROM models WHERE name = :name AND (id = @id OR parent = $id) AND note = ':id -- x' -- :missing\n/* @missing */")
+        .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT * FROM models WHERE name = ?1 AND (id = ?2 OR parent = ?2) AND note = ':id -- x' -- :missing\n/* @missing */"
+    );
+    assert_eq!(values, vec![Value::Text("gpt".to_string()), Value::Integer(1)]);
+
+    let missing = params.resolve("SELECT * FROM models WHERE provider = :provider").unwrap_err();
+    assert!(matches!(missing, DatabaseError::InvalidData { ref message } if message.contains(":provider")));
+
+    let mixed = params.resolve("SELECT * FROM models WHERE id = :id AND name = ?").unwrap_err();
+    assert!(matches!(mixed, DatabaseError::InvalidData { .. }));
+}
+
+#[tokio::test]
+async fn test_named_params_from_struct() {
+    let db = memory_database().await;
+    let model = NewModel {
+        name: "llama-3",
+        provider: "meta",
+        context_length: Some(8192),
+        tags: vec!["chat", "open"],
+        unused: true,
+    };
+
+    db.execute_named(
+        "INSERT INTO models (name, provider, context_length, tags) VALUES (:name, :provider, :context_length, :tags)",
+        &model,
+    )
+    .await
+    .unwrap();
+
+    let row: (String, i64, String) = db
+        .fetch_one_named(
+            "SELECT provider, context_length, tags FROM models WHERE name = :name",
+            named_params! { "name" => "llama-3" },
+        )
+        .await
+        .unwrap();
+    assert_eq!(row.0, "meta");
+    assert_eq!(row.1, 8192);
+    assert_eq!(serde_json::from_str::<Vec<String>>(&row.2).unwrap(), vec!["chat", "open"]);
+
+    // Non-object values cannot supply names
+    let err = db.execute_named("SELECT :x", &vec![1, 2]).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }));
+}
+
+#[tokio::test]
+async fn test_named_params_from_map() {
+    let db = memory_database().await;
+    for (name, provider, context_length) in [("a", "openai", 4096), ("b", "openai", 128000), ("c", "meta", 8192)] {
+        db.execute_named(
+            "INSERT INTO models (name, provider, context_length) VALUES (@name, @provider, @context_length)",
+            named_params! { "name" => name, "provider" => provider, "context_length" => context_length },
+        )
+        .await
+        .unwrap();
+    }
+
+    let mut map = HashMap::new();
+    map.insert("provider", Value::from("openai"));
+    map.insert("min_context", Value::from(8000));
+    let params = NamedParams::from(map);
+
+    let names: Vec<(String,)> = db
+        .fetch_all_named(
+            "SELECT name FROM models WHERE provider = :provider AND context_length >= :min_context ORDER BY name",
+            &params,
+        )
+        .await
+        .unwrap();
+    assert_eq!(names, vec![("b".to_string(),)]);
+
+    let rows = db.query_named("SELECT name FROM models WHERE provider = :provider", &params).await.unwrap();
+    assert_eq!(rows.len(), 2);
+
+    // Serialize maps work too, and prefixed keys are accepted
+    let mut serialized = HashMap::new();
+    serialized.insert("name", "zzz");
+    let none: Option<(String,)> = db
+        .fetch_optional_named("SELECT name FROM models WHERE name = :name", &serialized)
+        .await
+        .unwrap();
+    assert!(none.is_none());
+
+    let prefixed = named_params! { ":name" => "c" };
+    let found: Option<(String,)> = db
+        .fetch_optional_named("SELECT provider FROM models WHERE name = :name", prefixed)
+        .await
+        .unwrap();
+    assert_eq!(found, Some(("meta".to_string(),)));
+}
@@ -0,0 +1,201 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use burncloud_database::{col, Database, DatabaseBuilder, DatabaseError, PageRequest, Query};
+
+// Tests for offset and keyset pagination
+
+#[derive(Debug, sqlx::FromRow, PartialEq)]
+struct Log {
+    id: i64,
+    level: String,
+    at: i64,
+}
+
+async fn setup(builder: DatabaseBuilder) -> Database {
+    let db = builder.in_memory().build().await.unwrap();
+    db.execute_query("CREATE TABLE logs (id INTEGER PRIMARY KEY, level TEXT NOT NULL, at INTEGER NOT NULL)")
+        .await
+        .unwrap();
+    // Timestamps repeat so keyset ordering has to break ties on id
+    let rows = (1..=25).map(|id| (id, if id % 5 == 0 { "error" } else { "info" }, id / 2));
+    db.insert_many("logs", &["id", "level", "at"], rows.map(|(id, level, at)| burncloud_database::params![id, level, at]))
+        .await
+        .unwrap();
+    db
+}
+
+fn ids(logs: &[Log]) -> Vec<i64> {
+    logs.iter().map(|log| log.id).collect()
+}
+
+#[tokio::test]
+async fn test_offset_pages_report_total_and_has_more() {
+    let db = setup(Database::builder()).await;
+    let query = "SELECT * FROM logs WHERE level = ? ORDER BY id;";
+
+    let page = db.paginate::<Log, _>(query, ["info"], &PageRequest::page(1, 8)).await.unwrap();
+    assert_eq!(page.total, Some(20));
+    assert!(page.has_more);
+    assert_eq!(page.next_cursor, None);
+    assert_eq!(ids(&page.items), vec![1, 2, 3, 4, 6, 7, 8, 9]);
+
+    let page = db.paginate::<Log, _>(query, ["info"], &PageRequest::page(3, 8)).await.unwrap();
+    assert_eq!(ids(&page.items), vec![21, 22, 23, 24]);
+    assert!(!page.has_more);
+
+    let page = db.paginate::<Log, _>(query, ["info"], &PageRequest::offset(100, 8)).await.unwrap();
+    assert!(page.items.is_empty());
+    assert_eq!(page.total, Some(20));
+
+    let err = db.paginate::<Log, _>(query, ["info"], &PageRequest::offset(0, 0)).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }));
+}
+
+#[tokio::test]
+async fn test_keyset_walks_every_row_once() {
+    let db = setup(Database::builder()).await;
+    for descending in [false, true] {
+        let mut request = PageRequest::keyset(["at", "id"], 7);
+        if descending {
+            request = request.descending();
+        }
+        let mut seen = Vec::new();
+        let mut pages = 0;
+        loop {
+            let page = db.paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request).await.unwrap();
+            assert_eq!(page.total, None);
+            pages += 1;
+            seen.extend(ids(&page.items));
+            match page.next_cursor {
+                Some(cursor) => {
+                    assert!(page.has_more);
+                    request = request.after(cursor);
+                }
+                None => {
+                    assert!(!page.has_more);
+                    break;
+                }
+            }
+        }
+        let mut expected: Vec<i64> = (1..=25).collect();
+        if descending {
+            expected.reverse();
+        }
+        assert_eq!(seen, expected);
+        assert_eq!(pages, 4);
+    }
+}
+
+#[tokio::test]
+async fn test_keyset_continues_past_new_rows() {
+    let db = setup(Database::builder()).await;
+    let request = PageRequest::keyset(["id"], 10);
+    let first = db.paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request).await.unwrap();
+    let cursor = first.next_cursor.unwrap();
+
+    // Rows inserted before the cursor do not shift the next page
+    db.execute_query("INSERT INTO logs (id, level, at) VALUES (0, 'info', 0)").await.unwrap();
+    let second = db
+        .paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request.clone().after(cursor))
+        .await
+        .unwrap();
+    assert_eq!(ids(&second.items), (11..=20).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_cursors_are_tamper_evident() {
+    let db = setup(Database::builder()).await;
+    let request = PageRequest::keyset(["id"], 5);
+    let cursor = db
+        .paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request)
+        .await
+        .unwrap()
+        .next_cursor
+        .unwrap();
+
+    let (_, signature) = cursor.split_once('.').unwrap();
+    let forged = format!("{}.{}", URL_SAFE_NO_PAD.encode("[20]"), signature);
+    let other_order = PageRequest::keyset(["id"], 5).descending();
+    for (request, cursor) in [
+        (request.clone(), forged),
+        (request.clone(), "not a cursor".to_string()),
+        (other_order, cursor.clone()),
+    ] {
+        let err = db
+            .paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request.after(cursor))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+    }
+
+    // A database with another secret rejects the cursor too
+    let other = setup(Database::builder()).await;
+    assert!(other
+        .paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request.clone().after(cursor.clone()))
+        .await
+        .is_err());
+
+    // Databases sharing a secret accept each other's cursors
+    let a = setup(Database::builder().cursor_secret("shared")).await;
+    let b = setup(Database::builder().cursor_secret("shared")).await;
+    let cursor = a
+        .paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request)
+        .await
+        .unwrap()
+        .next_cursor
+        .unwrap();
+    let page = b
+        .paginate::<Log, _>("SELECT * FROM logs", Vec::<i64>::new(), &request.after(cursor))
+        .await
+        .unwrap();
+    assert_eq!(ids(&page.items), vec![6, 7, 8, 9, 10]);
+}
+
+#[tokio::test]
+async fn test_select_builder_paginates() {
+    let db = setup(Database::builder()).await;
+    let select = Query::select("logs").filter(col("level").eq("error")).order_by("id").limit(1);
+
+    let page = select.paginate::<Log>(&db, &PageRequest::offset(1, 2)).await.unwrap();
+    assert_eq!(ids(&page.items), vec![10, 15]);
+    assert_eq!(page.total, Some(5));
+    assert!(page.has_more);
+
+    let page = select.paginate::<Log>(&db, &PageRequest::keyset(["id"], 3).descending()).await.unwrap();
+    assert_eq!(ids(&page.items), vec![25, 20, 15]);
+    let summary = page.map(|log| log.level);
+    assert_eq!(summary.items, vec!["error"; 3]);
+    assert!(summary.has_more);
+}
+
+#[tokio::test]
+async fn test_queries_ending_in_comments_and_cursor_binding() {
+    let db = setup(Database::builder()).await;
+    let query = "SELECT * FROM logs WHERE level = ? ORDER BY id -- every level";
+
+    let page = db.paginate::<Log, _>(query, ["error"], &PageRequest::offset(0, 2)).await.unwrap();
+    assert_eq!(ids(&page.items), vec![5, 10]);
+    assert_eq!(page.total, Some(5));
+
+    let request = PageRequest::keyset(["id"], 2);
+    let cursor = db
+        .paginate::<Log, _>(query, ["error"], &request)
+        .await
+        .unwrap()
+        .next_cursor
+        .unwrap();
+    let page = db
+        .paginate::<Log, _>(query, ["error"], &request.clone().after(cursor.clone()))
+        .await
+        .unwrap();
+    assert_eq!(ids(&page.items), vec![15, 20]);
+
+    // The cursor is bound to the query and its parameters
+    for (query, level) in [(query, "info"), ("SELECT * FROM logs WHERE level = ?", "error")] {
+        let err = db
+            .paginate::<Log, _>(query, [level], &request.clone().after(cursor.clone()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+    }
+}
@@ -0,0 +1,86 @@
+use burncloud_database::sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+use burncloud_database::{DatabaseBuilder, PragmaProfile, TempStore};
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Tests for the PRAGMA profile applied to pooled connections
+
+#[tokio::test]
+async fn test_default_pragma_profile() {
+    let temp_dir = TempDir::new().unwrap();
+    let db = DatabaseBuilder::new()
+        .path(temp_dir.path().join("pragmas.db"))
+        .build()
+        .await
+        .unwrap();
+
+    let pragmas = db.effective_pragmas().await.unwrap();
+    assert_eq!(pragmas.journal_mode, "WAL");
+    assert!(pragmas.foreign_keys);
+    assert_eq!(pragmas.busy_timeout, Duration::from_secs(5));
+    assert_eq!(pragmas.synchronous, "NORMAL");
+    assert_eq!(pragmas.cache_size, -16 * 1024);
+    assert_eq!(pragmas.mmap_size, 256 * 1024 * 1024);
+    assert_eq!(pragmas.temp_store, "MEMORY");
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_custom_pragma_profile() {
+    let temp_dir = TempDir::new().unwrap();
+    let profile = PragmaProfile {
+        journal_mode: SqliteJournalMode::Delete,
+        foreign_keys: false,
+        busy_timeout: Duration::from_millis(250),
+        synchronous: SqliteSynchronous::Full,
+        cache_size: 500,
+        mmap_size: 0,
+        temp_store: TempStore::File,
+    };
+    let db = DatabaseBuilder::new()
+        .path(temp_dir.path().join("custom.db"))
+        .pragmas(profile)
+        .build()
+        .await
+        .unwrap();
+
+    assert_eq!(db.pragma_profile().cache_size, 500);
+
+    // Every pooled connection gets the profile, not just the first one
+    let pool = db.connection().unwrap().pool();
+    let mut connections = Vec::new();
+    for _ in 0..3 {
+        connections.push(pool.acquire().await.unwrap());
+    }
+    for conn in connections.iter_mut() {
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(&mut **conn).await.unwrap();
+        assert_eq!(busy_timeout, 250);
+    }
+    drop(connections);
+
+    let pragmas = db.effective_pragmas().await.unwrap();
+    assert_eq!(pragmas.journal_mode, "DELETE");
+    assert!(!pragmas.foreign_keys);
+    assert_eq!(pragmas.synchronous, "FULL");
+    assert_eq!(pragmas.cache_size, 500);
+    assert_eq!(pragmas.mmap_size, 0);
+    assert_eq!(pragmas.temp_store, "FILE");
+
+    db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_foreign_keys_enforced_by_default() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+
+    db.execute_query("CREATE TABLE models (id INTEGER PRIMARY KEY)").await.unwrap();
+    db.execute_query("CREATE TABLE model_files (id INTEGER PRIMARY KEY, model_id INTEGER NOT NULL REFERENCES models(id))")
+        .await
+        .unwrap();
+
+    let orphan = db.execute_query("INSERT INTO model_files (model_id) VALUES (42)").await;
+    assert!(orphan.is_err(), "Foreign key violation should be rejected");
+
+    db.close().await.unwrap();
+}
@@ -0,0 +1,168 @@
+use burncloud_database::{col, params, Condition, Database, DatabaseBuilder, DatabaseError, OnConflict, Query, Value};
+use serde::{Deserialize, Serialize};
+
+// Tests for the fluent SELECT/INSERT/UPDATE/DELETE builder
+
+#[test]
+fn test_builders_compile_to_sql_and_params() {
+    let (sql, params) = Query::select("models")
+        .filter(col("status").eq("ready"))
+        .filter(col("name").contains("50%_off"))
+        .filter(!(col("size").between(1, 10).or(col("size").is_null())))
+        .order_by("provider")
+        .order_by_desc("size")
+        .offset(40)
+        .build();
+    assert_eq!(
+        sql,
+        r#"SELECT * FROM "models" WHERE "status" = ? AND "name" LIKE ? ESCAPE '\' AND NOT ("size" BETWEEN ? AND ? OR "size" IS NULL) ORDER BY "provider", "size" DESC LIMIT -1 OFFSET 40"#
+    );
+    assert_eq!(params, vec![Value::from("ready"), Value::from("%50\\%\\_off%"), Value::from(1), Value::from(10)]);
+
+    let (sql, params) = Query::select("models")
+        .filter(Condition::raw("json_array_length(tags) > ? OR provider = ?", params![2, "meta"]))
+        .filter(col("provider").not_in(Vec::<String>::new()))
+        .build_count();
+    assert_eq!(
+        sql,
+        r#"SELECT COUNT(*) FROM "models" WHERE (json_array_length(tags) > ? OR provider = ?) AND "provider" NOT IN ()"#
+    );
+    assert_eq!(params.len(), 2);
+
+    let (sql, params) = Query::update("models").set("status", "ready").filter(col("id").eq(7)).build().unwrap();
+    assert_eq!(sql, r#"UPDATE "models" SET "status" = ? WHERE "id" = ?"#);
+    assert_eq!(params, vec![Value::from("ready"), Value::from(7)]);
+    assert!(matches!(
+        Query::update("models").filter(col("id").eq(7)).build(),
+        Err(DatabaseError::InvalidData { .. })
+    ));
+
+    // Dotted names are one identifier, not table.column
+    assert_eq!(Query::delete("models").filter(col("models.id").eq(1)).build().0, r#"DELETE FROM "models" WHERE "models.id" = ?"#);
+
+    let (sql, _) = Query::insert("we\"ird").on_conflict(OnConflict::Ignore).value("a", 1).value("b", None::<i64>).build();
+    assert_eq!(sql, r#"INSERT OR IGNORE INTO "we""ird" ("a", "b") VALUES (?, ?)"#);
+    assert_eq!(Query::delete("models").build().0, r#"DELETE FROM "models""#);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+struct Model {
+    id: i64,
+    provider: String,
+    name: String,
+    size: i64,
+    status: String,
+}
+
+async fn database_with_models() -> Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE models (id INTEGER PRIMARY KEY, provider TEXT, name TEXT, size INTEGER, status TEXT, tags TEXT)",
+    )
+    .await
+    .unwrap();
+    for (i, (provider, name, size, status)) in [
+        ("meta", "llama-3-8b", 8, "ready"),
+        ("meta", "llama-3-70b", 70, "downloading"),
+        ("mistral", "mistral-7b", 7, "ready"),
+        ("openai", "gpt_4o", 0, "ready"),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        Query::insert("models")
+            .value("id", i as i64 + 1)
+            .value("provider", provider)
+            .value("name", name)
+            .value("size", size)
+            .value("status", status)
+            .execute(&db)
+            .await
+            .unwrap();
+    }
+    db
+}
+
+#[tokio::test]
+async fn test_dynamic_filters_execute_through_database() {
+    let db = database_with_models().await;
+
+    // A search screen adds filters only for the fields the user filled in
+    let search = |provider: Option<&str>, max_size: Option<i64>, text: Option<&str>| {
+        let mut query = Query::select("models").filter(col("status").eq("ready")).order_by("id");
+        if let Some(provider) = provider {
+            query = query.filter(col("provider").eq(provider));
+        }
+        if let Some(max_size) = max_size {
+            query = query.filter(col("size").le(max_size));
+        }
+        if let Some(text) = text {
+            query = query.filter(col("name").contains(text));
+        }
+        query
+    };
+
+    let ready: Vec<Model> = search(None, None, None).fetch_all(&db).await.unwrap();
+    assert_eq!(ready.iter().map(|m| m.id).collect::<Vec<_>>(), [1, 3, 4]);
+
+    let small_meta: Vec<Model> = search(Some("meta"), Some(10), None).fetch_all(&db).await.unwrap();
+    assert_eq!(small_meta.len(), 1);
+    assert_eq!(small_meta[0].name, "llama-3-8b");
+
+    // "_" is matched literally, so "t_4" only finds gpt_4o
+    assert_eq!(search(None, None, Some("t_4")).count(&db).await.unwrap(), 1);
+    assert_eq!(search(None, None, Some("LLAMA")).count(&db).await.unwrap(), 1);
+
+    let names: Vec<(String,)> = Query::select("models")
+        .columns(["name"])
+        .filter(col("provider").is_in(["meta", "mistral"]))
+        .order_by_desc("size")
+        .limit(2)
+        .offset(1)
+        .fetch_all(&db)
+        .await
+        .unwrap();
+    assert_eq!(names, vec![("llama-3-8b".to_string(),), ("mistral-7b".to_string(),)]);
+
+    let first: Model = Query::select("models").filter(col("size").gt(50)).fetch_one(&db).await.unwrap();
+    assert_eq!(first.id, 2);
+    let missing: Option<Model> = Query::select("models").filter(col("size").gt(500)).fetch_optional(&db).await.unwrap();
+    assert!(missing.is_none());
+    let as_serde: Vec<Model> = Query::select("models").filter(col("provider").ne("meta")).fetch_all_as(&db).await.unwrap();
+    assert_eq!(as_serde.len(), 2);
+}
+
+#[tokio::test]
+async fn test_insert_update_delete_builders() {
+    let db = database_with_models().await;
+
+    #[derive(Serialize)]
+    struct NewModel<'a> {
+        provider: &'a str,
+        name: &'a str,
+        size: i64,
+        status: &'a str,
+        tags: Vec<&'a str>,
+    }
+    let insert = Query::insert("models")
+        .record(&NewModel { provider: "qwen", name: "qwen-2", size: 72, status: "ready", tags: vec!["chat"] })
+        .unwrap();
+    // Columns follow the struct's field order
+    assert!(insert.build().0.starts_with(r#"INSERT INTO "models" ("provider", "name", "size", "status", "tags")"#));
+    let inserted = insert.execute(&db).await.unwrap();
+    assert_eq!(inserted, 1);
+    let tags: String = db.fetch_scalar("SELECT tags FROM models WHERE provider = 'qwen'").await.unwrap();
+    assert_eq!(tags, "[\"chat\"]");
+
+    let updated = Query::update("models")
+        .set("status", "archived")
+        .filter(col("provider").eq("meta").and(col("size").ge(70)))
+        .execute(&db)
+        .await
+        .unwrap();
+    assert_eq!(updated, 1);
+
+    let deleted = Query::delete("models").filter(col("status").eq("archived")).execute(&db).await.unwrap();
+    assert_eq!(deleted, 1);
+    assert_eq!(Query::select("models").count(&db).await.unwrap(), 4);
+}
@@ -0,0 +1,160 @@
+use burncloud_database::repository::serialize_columns;
+use burncloud_database::sqlx::sqlite::SqliteRow;
+use burncloud_database::{from_row, Database, DatabaseBuilder, DatabaseError, Entity, ErrorKind, Repository, Result, Value};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+// Tests for the generic Entity repository
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Provider {
+    id: Option<i64>,
+    name: String,
+    #[serde(rename = "base_url")]
+    url: String,
+    enabled: bool,
+    settings: Option<ProviderSettings>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ProviderSettings {
+    timeout: u32,
+}
+
+impl Entity for Provider {
+    type Id = i64;
+    const TABLE: &'static str = "providers";
+    const COLUMNS: &'static [&'static str] = &["id", "name", "base_url", "enabled", "settings", "created_at"];
+
+    fn column_values(&self) -> Result<Vec<Value>> {
+        serialize_columns(self, Self::COLUMNS)
+    }
+
+    fn from_sql_row(row: &SqliteRow) -> Result<Self> {
+        from_row(row)
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Setting {
+    key: String,
+    value: String,
+}
+
+impl Entity for Setting {
+    type Id = String;
+    const TABLE: &'static str = "app settings";
+    const PRIMARY_KEY: &'static str = "key";
+    const COLUMNS: &'static [&'static str] = &["key", "value"];
+
+    fn column_values(&self) -> Result<Vec<Value>> {
+        serialize_columns(self, Self::COLUMNS)
+    }
+
+    fn from_sql_row(row: &SqliteRow) -> Result<Self> {
+        from_row(row)
+    }
+}
+
+async fn database() -> Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_script(
+        "CREATE TABLE providers (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE,
+            base_url TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL,
+            settings TEXT,
+            created_at TEXT NOT NULL
+        );
+        CREATE TABLE \"app settings\" (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+fn provider(name: &str) -> Provider {
+    Provider {
+        id: None,
+        name: name.to_string(),
+        url: format!("https://api.{}.com", name),
+        enabled: true,
+        settings: Some(ProviderSettings { timeout: 30 }),
+        created_at: Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn test_repository_crud() {
+    let db = database().await;
+    let providers: Repository<Provider> = db.repository();
+
+    let openai = providers.insert(&provider("openai")).await.unwrap();
+    assert_eq!(openai.id, Some(1));
+    assert_eq!(openai, Provider { id: Some(1), ..provider("openai") });
+    let mut anthropic = providers.insert(&provider("anthropic")).await.unwrap();
+    assert_eq!(anthropic.id, Some(2));
+
+    assert_eq!(providers.get(1).await.unwrap(), Some(openai.clone()));
+    assert_eq!(providers.get(42).await.unwrap(), None);
+    assert_eq!(providers.count().await.unwrap(), 2);
+    assert!(providers.exists(2).await.unwrap());
+    assert!(!providers.exists(3).await.unwrap());
+
+    anthropic.enabled = false;
+    anthropic.settings = None;
+    assert!(providers.update(&anthropic).await.unwrap());
+    assert_eq!(providers.get(2).await.unwrap(), Some(anthropic.clone()));
+
+    let listed = providers.list().await.unwrap();
+    assert_eq!(listed, vec![openai.clone(), anthropic.clone()]);
+
+    assert!(providers.delete(1).await.unwrap());
+    assert!(!providers.delete(1).await.unwrap());
+    let missing = Provider { id: Some(1), ..openai };
+    assert!(!providers.update(&missing).await.unwrap());
+    assert_eq!(providers.count().await.unwrap(), 1);
+
+    // Explicit ids are inserted as given; constraint errors pass through
+    let explicit = providers.insert(&Provider { id: Some(10), ..provider("mistral") }).await.unwrap();
+    assert_eq!(explicit.id, Some(10));
+    let err = providers.insert(&provider("mistral")).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+}
+
+#[tokio::test]
+async fn test_repository_with_text_keys_and_missing_columns() {
+    let db = database().await;
+    let settings = db.repository::<Setting>();
+
+    let theme = Setting { key: "theme".to_string(), value: "dark".to_string() };
+    assert_eq!(settings.insert(&theme).await.unwrap(), theme);
+    assert!(settings.update(&Setting { key: "theme".to_string(), value: "light".to_string() }).await.unwrap());
+    assert_eq!(settings.get("theme".to_string()).await.unwrap().unwrap().value, "light");
+    assert!(settings.exists("theme".to_string()).await.unwrap());
+    assert!(settings.delete("theme".to_string()).await.unwrap());
+    assert_eq!(settings.count().await.unwrap(), 0);
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Broken {
+        key: String,
+    }
+    impl Entity for Broken {
+        type Id = String;
+        const TABLE: &'static str = "app settings";
+        const PRIMARY_KEY: &'static str = "key";
+        const COLUMNS: &'static [&'static str] = &["key", "value"];
+
+        fn column_values(&self) -> Result<Vec<Value>> {
+            serialize_columns(self, Self::COLUMNS)
+        }
+
+        fn from_sql_row(row: &SqliteRow) -> Result<Self> {
+            from_row(row)
+        }
+    }
+    let err = db.repository::<Broken>().insert(&Broken { key: "x".to_string() }).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+}
@@ -0,0 +1,135 @@
+use burncloud_database::{Database, DatabaseBuilder, ErrorKind, PragmaProfile, RetryPolicy, TransactionBehavior};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Tests for retrying busy/locked errors
+// busy_timeout is 0 so that lock contention fails immediately instead of waiting in SQLite
+
+async fn open(path: &Path, policy: RetryPolicy) -> Database {
+    let pragmas = PragmaProfile {
+        busy_timeout: Duration::ZERO,
+        ..PragmaProfile::default()
+    };
+    DatabaseBuilder::new()
+        .path(path)
+        .pragmas(pragmas)
+        .retry_policy(policy)
+        .build()
+        .await
+        .unwrap()
+}
+
+fn patient_policy() -> RetryPolicy {
+    RetryPolicy::default()
+        .with_max_attempts(50)
+        .with_backoff(Duration::from_millis(10), Duration::from_millis(50))
+}
+
+#[test]
+fn test_backoff_grows_exponentially_up_to_the_cap() {
+    let policy = RetryPolicy::default()
+        .with_backoff(Duration::from_millis(10), Duration::from_millis(100))
+        .with_jitter(false);
+
+    let delays: Vec<u128> = (1..=6).map(|attempt| policy.delay_for(attempt).as_millis()).collect();
+    assert_eq!(delays, vec![10, 20, 40, 80, 100, 100]);
+    assert_eq!(RetryPolicy::none().max_attempts, 1);
+}
+
+#[test]
+fn test_unbounded_backoff_cap_does_not_panic() {
+    let policy = RetryPolicy::default().with_backoff(Duration::from_secs(1), Duration::MAX);
+    assert_eq!(policy.delay_for(1), Duration::from_secs(1));
+    assert_eq!(policy.delay_for(u32::MAX), Duration::MAX);
+
+    let policy = RetryPolicy {
+        max_backoff: Duration::MAX,
+        ..RetryPolicy::default()
+    };
+    assert!(policy.delay_for(2000) > Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_busy_write_is_retried_until_the_lock_is_released() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("retry.db");
+    let plain = open(&path, RetryPolicy::none()).await;
+    let retrying = open(&path, patient_policy()).await;
+    plain.execute_query("CREATE TABLE events (id INTEGER PRIMARY KEY, source TEXT)").await.unwrap();
+
+    let mut holder = plain.begin_with(TransactionBehavior::Immediate).await.unwrap();
+    holder.execute_query("INSERT INTO events (source) VALUES ('holder')").await.unwrap();
+
+    // Without retries the write fails straight away
+    let err = plain.execute_query("INSERT INTO events (source) VALUES ('plain')").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Busy, "expected SQLITE_BUSY, got {:?}", err);
+
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        holder.commit().await.unwrap();
+    });
+
+    retrying.execute_query("INSERT INTO events (source) VALUES ('retried')").await.unwrap();
+    release.await.unwrap();
+
+    let count: i64 = retrying.fetch_scalar("SELECT COUNT(*) FROM events").await.unwrap();
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn test_non_retryable_errors_are_not_retried() {
+    let db = DatabaseBuilder::new().in_memory().retry_policy(patient_policy()).build().await.unwrap();
+
+    let attempts = AtomicU32::new(0);
+    let result = db
+        .retry_policy()
+        .run(|| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            db.execute_query("SELEC 1")
+        })
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_retry_transaction_restarts_after_stale_snapshot() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("snapshot.db");
+    let db = open(&path, patient_policy()).await;
+    let other = Arc::new(open(&path, RetryPolicy::none()).await);
+    db.execute_query("CREATE TABLE counters (name TEXT PRIMARY KEY, value INTEGER NOT NULL)").await.unwrap();
+    db.execute_query("INSERT INTO counters VALUES ('requests', 0)").await.unwrap();
+
+    let attempts = Arc::new(AtomicU32::new(0));
+    let result = db
+        .retry_transaction(|tx| {
+            let attempts = Arc::clone(&attempts);
+            let other = Arc::clone(&other);
+            Box::pin(async move {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                let value: i64 = tx.fetch_scalar("SELECT value FROM counters WHERE name = 'requests'").await?;
+
+                // Another writer commits after our read snapshot was taken, so upgrading this
+                // deferred transaction to a writer fails with SQLITE_BUSY_SNAPSHOT
+                if attempt == 1 {
+                    other.execute_query("UPDATE counters SET value = value + 10").await?;
+                }
+
+                tx.execute_query_with_params("UPDATE counters SET value = ? WHERE name = 'requests'", vec![value + 1])
+                    .await?;
+                Ok(value + 1)
+            })
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(result, 11);
+    let stored: i64 = db.fetch_scalar("SELECT value FROM counters").await.unwrap();
+    assert_eq!(stored, 11);
+}
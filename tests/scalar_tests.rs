@@ -0,0 +1,50 @@
+use burncloud_database::{params, Database, DatabaseBuilder};
+
+// Tests for the fetch_scalar and fetch_optional_scalar helpers
+
+async fn memory_database() -> Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query("CREATE TABLE samples (id INTEGER PRIMARY KEY, name TEXT, size INTEGER, score REAL)")
+        .await
+        .unwrap();
+    db
+}
+
+#[tokio::test]
+async fn test_scalar_fetches() {
+    let db = memory_database().await;
+    db.execute_query_with_params(
+        "INSERT INTO samples (name, size) VALUES (?, ?), (?, ?), (?, ?)",
+        params!["a", 1, "b", 2, "c", 3],
+    )
+    .await
+    .unwrap();
+
+    let count: i64 = db.fetch_scalar("SELECT COUNT(*) FROM samples").await.unwrap();
+    assert_eq!(count, 3);
+
+    let larger: i64 = db
+        .fetch_scalar_with_params("SELECT COUNT(*) FROM samples WHERE size > ?", params![1])
+        .await
+        .unwrap();
+    assert_eq!(larger, 2);
+
+    let name: Option<String> = db
+        .fetch_optional_scalar_with_params("SELECT name FROM samples WHERE size = ?", params![3])
+        .await
+        .unwrap();
+    assert_eq!(name.as_deref(), Some("c"));
+
+    let missing: Option<String> = db
+        .fetch_optional_scalar("SELECT name FROM samples WHERE size > 100")
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+
+    // A NULL column decodes into Option<T> on a present row
+    let score: Option<f64> = db
+        .fetch_scalar_with_params("SELECT score FROM samples WHERE name = ?", params!["a"])
+        .await
+        .unwrap();
+    assert!(score.is_none());
+}
@@ -0,0 +1,132 @@
+use burncloud_database::{split_statements, DatabaseBuilder, DatabaseError, ErrorKind};
+use tempfile::TempDir;
+
+// Tests for running multi-statement SQL scripts
+
+const SEED: &str = "
+-- Seed data; semicolons in comments don't count;
+CREATE TABLE providers (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE, note TEXT);
+CREATE TABLE audit (message TEXT NOT NULL);
+
+/* Triggers keep their bodies together; */
+CREATE TRIGGER providers_audit AFTER INSERT ON providers
+BEGIN
+    INSERT INTO audit (message) VALUES ('added ' || NEW.name);
+    INSERT INTO audit (message) VALUES (CASE WHEN NEW.note IS NULL THEN 'no note' ELSE 'note' END);
+END;
+
+INSERT INTO providers (name, note) VALUES ('openai', 'it''s; quoted');
+INSERT INTO \"providers\" (name) VALUES ('anthropic');;
+";
+
+#[test]
+fn test_split_statements() {
+    let statements = split_statements(SEED);
+    assert_eq!(statements.len(), 5);
+    assert!(statements[0].starts_with("CREATE TABLE providers"));
+    assert!(statements[2].starts_with("CREATE TRIGGER providers_audit"));
+    assert!(statements[2].ends_with("END"));
+    assert_eq!(statements[3], "INSERT INTO providers (name, note) VALUES ('openai', 'it''s; quoted')");
+    assert_eq!(statements[4], "INSERT INTO \"providers\" (name) VALUES ('anthropic')");
+
+    assert_eq!(split_statements("SELECT 1; -- trailing comment"), ["SELECT 1"]);
+    assert_eq!(split_statements("SELECT [a;b] FROM t\n; SELECT 'é;'"), ["SELECT [a;b] FROM t", "SELECT 'é;'"]);
+    assert_eq!(split_statements("BEGIN; SELECT 1; COMMIT"), ["BEGIN", "SELECT 1", "COMMIT"]);
+    assert!(split_statements(" ;\n-- nothing here\n/* or here */").is_empty());
+}
+
+#[tokio::test]
+async fn test_execute_script_runs_every_statement() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    assert_eq!(db.execute_script(SEED).await.unwrap(), 5);
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM providers ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("openai".to_string(),), ("anthropic".to_string(),)]);
+    let audit: Vec<(String,)> = db.fetch_all("SELECT message FROM audit ORDER BY rowid").await.unwrap();
+    assert_eq!(audit.len(), 4);
+    assert_eq!(audit[3].0, "no note");
+
+    // Scripts can also be loaded from disk
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("fixup.sql");
+    std::fs::write(&path, "UPDATE providers SET note = 'fixed';\nDELETE FROM audit;\n").unwrap();
+    assert_eq!(db.execute_script_file(&path).await.unwrap(), 2);
+    let count: i64 = db.fetch_scalar("SELECT COUNT(*) FROM audit").await.unwrap();
+    assert_eq!(count, 0);
+
+    let err = db.execute_script_file(dir.path().join("missing.sql")).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::Io(_)));
+}
+
+#[tokio::test]
+async fn test_failed_script_rolls_back_and_reports_statement() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query("CREATE TABLE providers (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE)").await.unwrap();
+
+    let script = "INSERT INTO providers (name) VALUES ('openai');\n\
+                  INSERT INTO providers (name) VALUES ('anthropic');\n\
+                  \n\
+                  INSERT INTO providers (name) VALUES ('openai');\n";
+    let err = db.execute_script(script).await.unwrap_err();
+    match &err {
+        DatabaseError::Script { statement, line, .. } => assert_eq!((*statement, *line), (3, 4)),
+        other => panic!("expected a script error, got {:?}", other),
+    }
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+    assert_eq!(err.query_context().unwrap().sql(), "INSERT INTO providers (name) VALUES ('openai')");
+    assert!(err.to_string().starts_with("Script statement 3 (line 4) failed"), "{}", err);
+
+    let count: i64 = db.fetch_scalar("SELECT COUNT(*) FROM providers").await.unwrap();
+    assert_eq!(count, 0);
+
+    // Inside a transaction a failed script only undoes its own statements
+    let mut tx = db.begin().await.unwrap();
+    tx.execute_query("INSERT INTO providers (name) VALUES ('kept')").await.unwrap();
+    let err = tx.execute_script(script).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UniqueViolation);
+    assert_eq!(tx.execute_script("INSERT INTO providers (name) VALUES ('also kept')").await.unwrap(), 1);
+    tx.commit().await.unwrap();
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM providers ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("kept".to_string(),), ("also kept".to_string(),)]);
+}
+
+#[tokio::test]
+async fn test_scripts_wrapped_in_their_own_transaction() {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+
+    // The shape of `sqlite3 .dump` output
+    let dump = "PRAGMA foreign_keys=OFF;\n\
+                BEGIN TRANSACTION;\n\
+                CREATE TABLE providers (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);\n\
+                INSERT INTO providers VALUES(1,'openai');\n\
+                COMMIT;\n";
+    assert_eq!(db.execute_script(dump).await.unwrap(), 3);
+    assert_eq!(db.execute_script("begin; INSERT INTO providers (name) VALUES ('anthropic'); end transaction").await.unwrap(), 1);
+
+    let mut tx = db.begin().await.unwrap();
+    let savepoints = "BEGIN IMMEDIATE;\n\
+                      SAVEPOINT fixup;\n\
+                      INSERT INTO providers (name) VALUES ('discarded');\n\
+                      ROLLBACK TO fixup;\n\
+                      RELEASE fixup;\n\
+                      COMMIT;";
+    assert_eq!(tx.execute_script(savepoints).await.unwrap(), 4);
+    tx.commit().await.unwrap();
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM providers ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("openai".to_string(),), ("anthropic".to_string(),)]);
+
+    // Transaction control that does not wrap the whole script is rejected before anything runs
+    for script in [
+        "INSERT INTO providers (name) VALUES ('early'); COMMIT; INSERT INTO providers (name) VALUES ('late')",
+        "BEGIN; INSERT INTO providers (name) VALUES ('early'); ROLLBACK",
+        "BEGIN; INSERT INTO providers (name) VALUES ('early')",
+        "INSERT INTO providers (name) VALUES ('early'); COMMIT",
+    ] {
+        let err = db.execute_script(script).await.unwrap_err();
+        assert!(matches!(err, DatabaseError::InvalidData { .. }), "{}: {:?}", script, err);
+    }
+    let count: i64 = db.fetch_scalar("SELECT COUNT(*) FROM providers").await.unwrap();
+    assert_eq!(count, 2);
+}
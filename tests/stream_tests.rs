@@ -0,0 +1,86 @@
+use burncloud_database::{params, Database, DatabaseBuilder, ErrorKind, Value};
+use futures::{StreamExt, TryStreamExt};
+
+// Tests for streaming rows instead of buffering whole result sets
+
+const ROWS: i64 = 20_000;
+
+async fn database_with_usage_records() -> Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query("CREATE TABLE usage_records (id INTEGER PRIMARY KEY, model TEXT NOT NULL, tokens INTEGER NOT NULL)")
+        .await
+        .unwrap();
+    db.execute_query_with_params(
+        "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < ?)
+         INSERT INTO usage_records (model, tokens) SELECT 'model-' || (n % 3), n FROM seq",
+        params![ROWS],
+    )
+    .await
+    .unwrap();
+    db
+}
+
+#[derive(sqlx::FromRow)]
+struct UsageRecord {
+    id: i64,
+    model: String,
+    tokens: i64,
+}
+
+#[tokio::test]
+async fn test_stream_yields_every_row_in_order() {
+    let db = database_with_usage_records().await;
+
+    let mut rows = db.stream::<UsageRecord, _>("SELECT id, model, tokens FROM usage_records ORDER BY id", params![]);
+    let mut count = 0;
+    let mut total_tokens = 0;
+    let mut last_id = 0;
+    while let Some(record) = rows.try_next().await.unwrap() {
+        assert!(record.id > last_id);
+        assert!(record.model.starts_with("model-"));
+        last_id = record.id;
+        total_tokens += record.tokens;
+        count += 1;
+    }
+    drop(rows);
+
+    assert_eq!(count, ROWS);
+    assert_eq!(total_tokens, ROWS * (ROWS + 1) / 2);
+}
+
+#[tokio::test]
+async fn test_stream_with_params_and_early_stop() {
+    let db = database_with_usage_records().await;
+
+    let first: Vec<(i64,)> = db
+        .stream::<(i64,), _>("SELECT tokens FROM usage_records WHERE model = ? ORDER BY id", params!["model-1"])
+        .take(3)
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(first, vec![(1,), (4,), (7,)]);
+
+    // Dropping a partially read stream returns its connection to the pool
+    let count: i64 = db.fetch_scalar("SELECT COUNT(*) FROM usage_records").await.unwrap();
+    assert_eq!(count, ROWS);
+
+    let mut tx = db.begin().await.unwrap();
+    tx.execute_query("DELETE FROM usage_records WHERE model <> 'model-0'").await.unwrap();
+    let remaining = tx
+        .stream::<(i64,), _>("SELECT id FROM usage_records", Vec::<Value>::new())
+        .try_fold(0, |n, _| async move { Ok(n + 1) })
+        .await
+        .unwrap();
+    assert_eq!(remaining, ROWS / 3);
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stream_errors_carry_query_context() {
+    let db = database_with_usage_records().await;
+
+    let mut rows = db.stream::<(i64,), _>("SELECT id FROM missing_table WHERE id > ?", params![1]);
+    let err = rows.next().await.unwrap().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert_eq!(err.query_context().unwrap().params(), ["integer"]);
+}
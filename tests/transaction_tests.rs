@@ -0,0 +1,308 @@
+use burncloud_database::{named_params, params, Database, DatabaseBuilder, DatabaseError, PragmaProfile, Result, Transaction, TransactionBehavior};
+use std::time::Duration;
+use tempfile::TempDir;
+
+// Tests for Database::transaction and the Transaction handle
+
+const SCHEMA: &str = "
+    CREATE TABLE models (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+    CREATE TABLE model_files (model_id INTEGER NOT NULL REFERENCES models(id), path TEXT NOT NULL);
+    CREATE TABLE model_tags (model_id INTEGER NOT NULL REFERENCES models(id), tag TEXT NOT NULL);
+";
+
+async fn file_database(dir: &TempDir) -> Database {
+    let pragmas = PragmaProfile {
+        busy_timeout: Duration::from_millis(100),
+        ..PragmaProfile::default()
+    };
+    let db = DatabaseBuilder::new()
+        .path(dir.path().join("tx.db"))
+        .pragmas(pragmas)
+        .build()
+        .await
+        .unwrap();
+    sqlx::raw_sql(SCHEMA).execute(db.connection().unwrap().pool()).await.unwrap();
+    db
+}
+
+// Stands in for library code that wants its own atomic scope and may be called from inside
+// a caller's transaction
+async fn register_model(tx: &mut Transaction, name: &str, files: &[&str]) -> Result<i64> {
+    let name = name.to_string();
+    let files: Vec<String> = files.iter().map(|f| f.to_string()).collect();
+    tx.transaction(move |tx| {
+        Box::pin(async move {
+            let id = tx
+                .execute_query_with_params("INSERT INTO models (name) VALUES (?)", params![name])
+                .await?
+                .last_insert_rowid();
+            for path in files {
+                if path.is_empty() {
+                    return Err(DatabaseError::InvalidData {
+                        message: "empty file path".to_string(),
+                    });
+                }
+                tx.execute_query_with_params("INSERT INTO model_files (model_id, path) VALUES (?, ?)", params![id, path])
+                    .await?;
+            }
+            Ok(id)
+        })
+    })
+    .await
+}
+
+async fn count(db: &Database, table: &str) -> i64 {
+    db.fetch_scalar(&format!("SELECT COUNT(*) FROM {}", table)).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_transaction_commits_on_ok() {
+    let dir = TempDir::new().unwrap();
+    let db = file_database(&dir).await;
+
+    let model_id = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                let id = tx
+                    .execute_query_with_params("INSERT INTO models (name) VALUES (?)", params!["llama-3"])
+                    .await?
+                    .last_insert_rowid();
+                for path in ["model.safetensors", "tokenizer.json"] {
+                    tx.execute_query_with_params("INSERT INTO model_files (model_id, path) VALUES (?, ?)", params![id, path])
+                        .await?;
+                }
+                tx.execute_named(
+                    "INSERT INTO model_tags (model_id, tag) VALUES (:id, :tag)",
+                    named_params! { "id" => id, "tag" => "chat" },
+                )
+                .await?;
+
+                // Reads inside the transaction see its own writes
+                let files: i64 = tx
+                    .fetch_scalar_with_params("SELECT COUNT(*) FROM model_files WHERE model_id = ?", params![id])
+                    .await?;
+                assert_eq!(files, 2);
+                Ok(id)
+            })
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(model_id, 1);
+    assert_eq!(count(&db, "models").await, 1);
+    assert_eq!(count(&db, "model_files").await, 2);
+    assert_eq!(count(&db, "model_tags").await, 1);
+}
+
+#[tokio::test]
+async fn test_transaction_rolls_back_on_err() {
+    let dir = TempDir::new().unwrap();
+    let db = file_database(&dir).await;
+    db.execute_query("INSERT INTO models (name) VALUES ('existing')").await.unwrap();
+
+    let result: Result<()> = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                tx.execute_query("INSERT INTO models (name) VALUES ('new')").await?;
+                tx.execute_query("INSERT INTO model_files (model_id, path) VALUES (2, 'a.bin')").await?;
+                // Violates the UNIQUE constraint, so everything above must be undone
+                tx.execute_query("INSERT INTO models (name) VALUES ('existing')").await?;
+                Ok(())
+            })
+        })
+        .await;
+
+    assert!(matches!(result, Err(DatabaseError::Query { .. })));
+    assert_eq!(count(&db, "models").await, 1);
+    assert_eq!(count(&db, "model_files").await, 0);
+
+    // Application errors roll back too
+    let result: Result<()> = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                tx.execute_query("INSERT INTO models (name) VALUES ('other')").await?;
+                Err(DatabaseError::InvalidData {
+                    message: "checksum mismatch".to_string(),
+                })
+            })
+        })
+        .await;
+    assert!(matches!(result, Err(DatabaseError::InvalidData { .. })));
+    assert_eq!(count(&db, "models").await, 1);
+}
+
+#[tokio::test]
+async fn test_explicit_begin_commit_rollback_and_drop() {
+    let dir = TempDir::new().unwrap();
+    let db = file_database(&dir).await;
+
+    let mut tx = db.begin().await.unwrap();
+    assert_eq!(tx.behavior(), TransactionBehavior::Deferred);
+    tx.execute_query("INSERT INTO models (name) VALUES ('committed')").await.unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = db.begin().await.unwrap();
+    tx.execute_query("INSERT INTO models (name) VALUES ('rolled-back')").await.unwrap();
+    tx.rollback().await.unwrap();
+
+    {
+        let mut tx = db.begin().await.unwrap();
+        tx.execute_query("INSERT INTO models (name) VALUES ('dropped')").await.unwrap();
+    }
+    // The rollback for a dropped transaction runs in the background
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM models ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("committed".to_string(),)]);
+
+    // The write lock was released, so new writers are not blocked
+    db.execute_query("INSERT INTO models (name) VALUES ('after')").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_immediate_transaction_takes_write_lock() {
+    let dir = TempDir::new().unwrap();
+    let db = file_database(&dir).await;
+
+    let mut writer = db.begin_with(TransactionBehavior::Immediate).await.unwrap();
+    writer.execute_query("INSERT INTO models (name) VALUES ('locked')").await.unwrap();
+
+    // A second IMMEDIATE transaction cannot start while the first holds the write lock...
+    let blocked = db.begin_with(TransactionBehavior::Immediate).await;
+    assert!(blocked.is_err());
+
+    // ...but a DEFERRED one can, and may read the last committed state
+    let mut reader = db.begin_with(TransactionBehavior::Deferred).await.unwrap();
+    let visible: i64 = reader.fetch_scalar("SELECT COUNT(*) FROM models").await.unwrap();
+    assert_eq!(visible, 0);
+    reader.rollback().await.unwrap();
+
+    writer.commit().await.unwrap();
+
+    let result = db
+        .transaction_with(TransactionBehavior::Immediate, |tx| {
+            Box::pin(async move { tx.fetch_scalar::<i64>("SELECT COUNT(*) FROM models").await })
+        })
+        .await
+        .unwrap();
+    assert_eq!(result, 1);
+}
+
+#[tokio::test]
+async fn test_nested_transactions_use_savepoints() {
+    let dir = TempDir::new().unwrap();
+    let db = file_database(&dir).await;
+
+    // A failing nested scope only undoes its own work; the caller decides what to do next
+    let imported = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                assert_eq!(tx.depth(), 0);
+                let mut imported = Vec::new();
+                imported.push(register_model(tx, "good", &["a.bin", "b.bin"]).await?);
+                assert!(register_model(tx, "broken", &["c.bin", ""]).await.is_err());
+                imported.push(register_model(tx, "also-good", &["d.bin"]).await?);
+                assert_eq!(tx.depth(), 0);
+                Ok(imported)
+            })
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(imported.len(), 2);
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM models ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("good".to_string(),), ("also-good".to_string(),)]);
+    assert_eq!(count(&db, "model_files").await, 3);
+
+    // A failing outer transaction discards released nested scopes as well
+    let result: Result<()> = db
+        .transaction(|tx| {
+            Box::pin(async move {
+                register_model(tx, "discarded", &["e.bin"]).await?;
+                tx.execute_query("INSERT INTO models (name) VALUES ('good')").await?;
+                Ok(())
+            })
+        })
+        .await;
+    assert!(result.is_err());
+    assert_eq!(count(&db, "models").await, 2);
+    assert_eq!(count(&db, "model_files").await, 3);
+}
+
+#[tokio::test]
+async fn test_savepoints_nest_several_levels() {
+    let dir = TempDir::new().unwrap();
+    let db = file_database(&dir).await;
+
+    let mut tx = db.begin().await.unwrap();
+    let depths = tx
+        .transaction(|tx| {
+            Box::pin(async move {
+                tx.execute_query("INSERT INTO models (name) VALUES ('level-1')").await?;
+                let inner = tx
+                    .transaction(|tx| {
+                        Box::pin(async move {
+                            tx.execute_query("INSERT INTO models (name) VALUES ('level-2')").await?;
+                            Ok(tx.depth())
+                        })
+                    })
+                    .await?;
+                Ok((tx.depth(), inner))
+            })
+        })
+        .await
+        .unwrap();
+    assert_eq!(depths, (1, 2));
+    assert_eq!(tx.depth(), 0);
+
+    let inside: i64 = tx.fetch_scalar("SELECT COUNT(*) FROM models").await.unwrap();
+    assert_eq!(inside, 2);
+    tx.rollback().await.unwrap();
+    assert_eq!(count(&db, "models").await, 0);
+}
+
+#[tokio::test]
+async fn test_cancelled_nested_scope_is_rolled_back() {
+    let dir = TempDir::new().unwrap();
+    let db = file_database(&dir).await;
+
+    let mut tx = db.begin().await.unwrap();
+    register_model(&mut tx, "outer", &[]).await.unwrap();
+    let nested = tx.transaction(|tx| {
+        Box::pin(async move {
+            tx.execute_query("INSERT INTO models (name) VALUES ('cancelled')").await?;
+            std::future::pending::<()>().await;
+            Ok(())
+        })
+    });
+    assert!(tokio::time::timeout(Duration::from_millis(50), nested).await.is_err());
+    assert_eq!(tx.depth(), 0);
+
+    register_model(&mut tx, "after", &["a.gguf"]).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let names: Vec<(String,)> = db.fetch_all("SELECT name FROM models ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![("outer".to_string(),), ("after".to_string(),)]);
+}
+
+#[test]
+fn test_drop_without_runtime_closes_connection() {
+    let dir = TempDir::new().unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let (db, tx) = runtime.block_on(async {
+        let db = file_database(&dir).await;
+        let mut tx = db.begin_with(TransactionBehavior::Immediate).await.unwrap();
+        tx.execute_query("INSERT INTO models (name) VALUES ('orphan')").await.unwrap();
+        (db, tx)
+    });
+
+    // Outside the runtime there is nothing to run ROLLBACK on
+    drop(tx);
+
+    runtime.block_on(async {
+        assert_eq!(count(&db, "models").await, 0);
+        // The write lock is gone, so another writer can start right away
+        let tx = db.begin_with(TransactionBehavior::Immediate).await.unwrap();
+        tx.commit().await.unwrap();
+    });
+}
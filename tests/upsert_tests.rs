@@ -0,0 +1,114 @@
+use burncloud_database::{DatabaseBuilder, DatabaseError, MergeRule, UpdatePolicy};
+use serde::Serialize;
+
+// Tests for INSERT ... ON CONFLICT DO UPDATE upserts
+
+#[derive(Serialize)]
+struct ModelSync<'a> {
+    provider: &'a str,
+    name: &'a str,
+    display_name: &'a str,
+    downloads: i64,
+    max_context: Option<i64>,
+    first_seen: &'a str,
+}
+
+async fn database_with_models() -> burncloud_database::Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE models (
+            id INTEGER PRIMARY KEY,
+            provider TEXT NOT NULL,
+            name TEXT NOT NULL,
+            display_name TEXT,
+            downloads INTEGER,
+            max_context INTEGER,
+            first_seen TEXT,
+            notes TEXT,
+            UNIQUE (provider, name)
+        )",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+#[tokio::test]
+async fn test_upsert_merges_columns_per_rule() {
+    let db = database_with_models().await;
+    let policy = UpdatePolicy::overwrite()
+        .with("downloads", MergeRule::Increment)
+        .with("max_context", MergeRule::Max)
+        .with("first_seen", MergeRule::Min);
+    let conflict = ["provider", "name"];
+
+    let first = ModelSync {
+        provider: "meta",
+        name: "llama-3",
+        display_name: "Llama 3",
+        downloads: 10,
+        max_context: None,
+        first_seen: "2024-05-01",
+    };
+    assert_eq!(db.upsert("models", &first, &conflict, &policy).await.unwrap(), 1);
+    db.execute_query("UPDATE models SET notes = 'curated'").await.unwrap();
+
+    let second = ModelSync {
+        display_name: "Llama 3 (8B)",
+        downloads: 5,
+        max_context: Some(8192),
+        first_seen: "2024-04-18",
+        ..first
+    };
+    assert_eq!(db.upsert("models", &second, &conflict, &policy).await.unwrap(), 1);
+    let third = ModelSync {
+        max_context: Some(4096),
+        first_seen: "2024-06-01",
+        ..second
+    };
+    db.upsert("models", &third, &conflict, &policy).await.unwrap();
+
+    let row: (i64, String, i64, i64, String, String) = db
+        .fetch_one("SELECT id, display_name, downloads, max_context, first_seen, notes FROM models")
+        .await
+        .unwrap();
+    // Columns the record doesn't mention, like notes, are never touched
+    assert_eq!(
+        row,
+        (1, "Llama 3 (8B)".to_string(), 20, 8192, "2024-04-18".to_string(), "curated".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_upsert_keep_existing_and_errors() {
+    let db = database_with_models().await;
+    let record = serde_json::json!({ "provider": "openai", "name": "gpt-4o", "display_name": "GPT-4o", "downloads": 1 });
+
+    let policy = UpdatePolicy::keep_existing().with("downloads", MergeRule::Increment);
+    db.upsert("models", &record, &["provider", "name"], &policy).await.unwrap();
+    let renamed = serde_json::json!({ "provider": "openai", "name": "gpt-4o", "display_name": "Renamed", "downloads": 2 });
+    db.upsert("models", &renamed, &["provider", "name"], &policy).await.unwrap();
+    let row: (String, i64) = db.fetch_one("SELECT display_name, downloads FROM models").await.unwrap();
+    assert_eq!(row, ("GPT-4o".to_string(), 3));
+
+    // With nothing to update the conflicting row is skipped
+    let affected = db
+        .upsert("models", &renamed, &["provider", "name"], &UpdatePolicy::keep_existing())
+        .await
+        .unwrap();
+    assert_eq!(affected, 0);
+
+    let mut tx = db.begin().await.unwrap();
+    assert_eq!(tx.upsert("models", &renamed, &["provider", "name"], &UpdatePolicy::overwrite()).await.unwrap(), 1);
+    tx.commit().await.unwrap();
+    let name: String = db.fetch_scalar("SELECT display_name FROM models").await.unwrap();
+    assert_eq!(name, "Renamed");
+
+    let err = db.upsert("models", &record, &["id"], &UpdatePolicy::overwrite()).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+    let err = db.upsert("models", &record, &[], &UpdatePolicy::overwrite()).await.unwrap_err();
+    assert!(matches!(err, DatabaseError::InvalidData { .. }), "{:?}", err);
+    // The conflict target must match a UNIQUE constraint
+    let err = db.upsert("models", &record, &["name"], &UpdatePolicy::overwrite()).await.unwrap_err();
+    assert!(err.to_string().contains("ON CONFLICT clause does not match"), "{}", err);
+}
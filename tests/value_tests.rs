@@ -0,0 +1,135 @@
+use burncloud_database::{params, Database, DatabaseBuilder, Value};
+use chrono::{TimeZone, Utc};
+use uuid::Uuid;
+
+// Tests for typed bind parameters and the params! macro
+
+async fn memory_database() -> Database {
+    let db = DatabaseBuilder::new().in_memory().build().await.unwrap();
+    db.execute_query(
+        "CREATE TABLE samples (
+            id INTEGER PRIMARY KEY,
+            name TEXT,
+            size INTEGER,
+            score REAL,
+            data BLOB,
+            active BOOLEAN,
+            meta TEXT,
+            uid TEXT,
+            created_at TEXT
+        )",
+    )
+    .await
+    .unwrap();
+    db
+}
+
+#[test]
+fn test_value_conversions() {
+    assert_eq!(Value::from(7_i32), Value::Integer(7));
+    assert_eq!(Value::from(7_u32), Value::Integer(7));
+    assert_eq!(Value::from(1.5_f32), Value::Real(1.5));
+    assert_eq!(Value::from("text"), Value::Text("text".to_string()));
+    assert_eq!(Value::from(vec![1_u8, 2]), Value::Blob(vec![1, 2]));
+    assert_eq!(Value::from(&b"ab"[..]), Value::Blob(b"ab".to_vec()));
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from(None::<String>), Value::Null);
+    assert_eq!(Value::from(Some(3_i64)), Value::Integer(3));
+    assert!(Value::Null.is_null());
+
+    let params = params![1, "two", 3.0, None::<i64>];
+    assert_eq!(params.len(), 4);
+    assert_eq!(params[1], Value::Text("two".to_string()));
+    assert!(params![].is_empty());
+}
+
+#[tokio::test]
+async fn test_typed_values_round_trip() {
+    let db = memory_database().await;
+    let uid = Uuid::new_v4();
+    let created_at = Utc.with_ymd_and_hms(2024, 5, 17, 8, 30, 0).unwrap();
+    let meta = serde_json::json!({ "provider": "openai", "tags": ["chat"] });
+
+    db.execute_query_with_params(
+        "INSERT INTO samples (name, size, score, data, active, meta, uid, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params!["gpt", 175_i64, 0.93, vec![0_u8, 159, 146, 150], true, meta.clone(), uid, created_at],
+    )
+    .await
+    .unwrap();
+
+    type Row = (String, i64, f64, Vec<u8>, bool, String, String, chrono::DateTime<Utc>);
+    let row: Row = db
+        .fetch_one("SELECT name, size, score, data, active, meta, uid, created_at FROM samples")
+        .await
+        .unwrap();
+
+    assert_eq!(row.0, "gpt");
+    assert_eq!(row.1, 175);
+    assert_eq!(row.2, 0.93);
+    assert_eq!(row.3, vec![0, 159, 146, 150]);
+    assert!(row.4);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&row.5).unwrap(), meta);
+    assert_eq!(row.6, uid.to_string());
+    assert_eq!(row.7, created_at);
+
+    // Integers must be stored with INTEGER affinity, not as text
+    let types: (String, String, String, String) = db
+        .fetch_one("SELECT typeof(size), typeof(score), typeof(data), typeof(active) FROM samples")
+        .await
+        .unwrap();
+    assert_eq!(types, ("integer".into(), "real".into(), "blob".into(), "integer".into()));
+}
+
+#[tokio::test]
+async fn test_null_and_numeric_comparisons() {
+    let db = memory_database().await;
+    for (name, size) in [("small", Some(7_i64)), ("large", Some(70)), ("unknown", None)] {
+        db.execute_query_with_params("INSERT INTO samples (name, size) VALUES (?, ?)", params![name, size])
+            .await
+            .unwrap();
+    }
+
+    // 70 > 8 numerically, although "70" < "8" as text
+    let rows = db
+        .query_with_params("SELECT name FROM samples WHERE size > ? ORDER BY name", params![8])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+
+    let missing: Vec<(String,)> = db
+        .fetch_all_with_params("SELECT name FROM samples WHERE size IS ?", params![None::<i64>])
+        .await
+        .unwrap();
+    assert_eq!(missing, vec![("unknown".to_string(),)]);
+}
+
+#[tokio::test]
+async fn test_parameterized_fetch_variants() {
+    let db = memory_database().await;
+    db.execute_query_with_params(
+        "INSERT INTO samples (name, size) VALUES (?, ?), (?, ?)",
+        params!["a", 1, "b", 2],
+    )
+    .await
+    .unwrap();
+
+    let one: (String,) = db
+        .fetch_one_with_params("SELECT name FROM samples WHERE size = ?", params![2])
+        .await
+        .unwrap();
+    assert_eq!(one.0, "b");
+
+    let none: Option<(String,)> = db
+        .fetch_optional_with_params("SELECT name FROM samples WHERE name = ?", params!["'; DROP TABLE samples; --"])
+        .await
+        .unwrap();
+    assert!(none.is_none());
+
+    // Existing callers passing strings keep working
+    let rows = db
+        .query_with_params("SELECT name FROM samples WHERE name = ?", vec!["a".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+}